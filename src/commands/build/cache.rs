@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::fs::create_file;
+use crate::utils::hashlib::calculate_file_sha256;
+
+/// The version of the build manifest format.
+const MANIFEST_VERSION: u32 = 3;
+
+/// Records the inputs every bin was last built from, so that bins whose
+/// inputs have not changed can be skipped.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq)]
+pub struct BuildManifest {
+    version: u32,
+    /// Version of shinc that built the bins.
+    shinc: String,
+    bins: BTreeMap<String, BinEntry>,
+}
+
+/// The inputs of a build shared by every bin.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BuildKey {
+    /// The sha256 of the configuration file.
+    pub config: String,
+    /// The `TERM_WIDTH` the help text is wrapped to.
+    pub term_width: Option<usize>,
+    /// The shell formatter and its version.
+    pub formatter: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq)]
+struct BinEntry {
+    key: BuildKey,
+    /// The sha256 of the entry file and every included file.
+    sources: BTreeMap<PathBuf, String>,
+    /// The tools declared with `@meta require-tools`.
+    tools: BTreeSet<String>,
+}
+
+impl Default for BuildManifest {
+    fn default() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            shinc: clap::crate_version!().to_string(),
+            bins: BTreeMap::new(),
+        }
+    }
+}
+
+impl BuildManifest {
+    /// Loads the manifest at `path`.
+    ///
+    /// A missing or unreadable manifest, or one written by another version of
+    /// shinc, is treated as empty.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let manifest = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .unwrap_or_default();
+        if manifest.version != MANIFEST_VERSION || manifest.shinc != clap::crate_version!() {
+            return Self::default();
+        }
+        manifest
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(create_file(path)?, self)?;
+        Ok(())
+    }
+
+    /// Returns `true` if `name` was built with the same `key` and none of its
+    /// sources has changed since.
+    pub fn is_fresh(&self, name: &str, key: &BuildKey) -> bool {
+        let Some(entry) = self.bins.get(name) else {
+            return false;
+        };
+        &entry.key == key
+            && entry.sources.iter().all(|(path, hash)| {
+                calculate_file_sha256(path).is_ok_and(|current| &current == hash)
+            })
+    }
+
+    pub fn record(
+        &mut self,
+        name: &str,
+        key: &BuildKey,
+        sources: &HashSet<PathBuf>,
+        tools: BTreeSet<String>,
+    ) -> anyhow::Result<()> {
+        let sources = sources
+            .iter()
+            .map(|path| Ok((path.clone(), calculate_file_sha256(path)?)))
+            .collect::<anyhow::Result<_>>()?;
+        self.bins.insert(name.to_string(), BinEntry { key: key.clone(), sources, tools });
+        Ok(())
+    }
+
+    /// Returns the tools required by `name`, or `None` if it has not been
+    /// built.
+    pub fn tools(&self, name: &str) -> Option<&BTreeSet<String>> {
+        self.bins.get(name).map(|entry| &entry.tools)
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.bins.remove(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_build_manifest_detects_changes() {
+        let temp_dir = std::env::temp_dir().join("test_build_manifest");
+        let source = temp_dir.join("main.sh");
+        create_file(&source).unwrap().write_all(b"echo hello\n").unwrap();
+        let sources = HashSet::from([source.clone()]);
+
+        let key = BuildKey {
+            config: "config".to_string(),
+            term_width: None,
+            formatter: "shfmt v3.8.0".to_string(),
+        };
+        let mut manifest = BuildManifest::default();
+        assert!(!manifest.is_fresh("main", &key));
+        let tools = BTreeSet::from(["curl".to_string()]);
+        manifest.record("main", &key, &sources, tools.clone()).unwrap();
+        assert!(manifest.is_fresh("main", &key));
+        assert_eq!(manifest.tools("main"), Some(&tools));
+        assert!(
+            !manifest.is_fresh("main", &BuildKey { config: "other".to_string(), ..key.clone() })
+        );
+        assert!(!manifest.is_fresh("main", &BuildKey { term_width: Some(80), ..key.clone() }));
+        assert!(
+            !manifest
+                .is_fresh("main", &BuildKey { formatter: "builtin".to_string(), ..key.clone() })
+        );
+
+        let manifest_file = temp_dir.join("manifest.json");
+        manifest.save(&manifest_file).unwrap();
+        assert_eq!(BuildManifest::load(&manifest_file), manifest);
+
+        create_file(&source).unwrap().write_all(b"echo world\n").unwrap();
+        assert!(!manifest.is_fresh("main", &key));
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
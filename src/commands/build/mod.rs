@@ -0,0 +1,400 @@
+pub mod cache;
+mod watch;
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use self::cache::{BuildKey, BuildManifest};
+use crate::config::Bin;
+use crate::prelude::*;
+use crate::utils::argc::lint::{Diagnostic, Severity};
+use crate::utils::argc::parser::{EventData, parse};
+use crate::utils::argc::tag::{self, ArgcTag};
+use crate::utils::formatter::identifier;
+use crate::utils::fs::{create_file, set_executable};
+use crate::utils::hashlib::calculate_file_sha256;
+use crate::utils::jobs;
+use crate::utils::shell::{self, fmt_shell, shellcheck};
+use crate::utils::sourcemap::{Mapping, SourceMap};
+use crate::utils::tips;
+
+/// Generate and build shell scripts.
+#[derive(clap::Parser, Debug)]
+pub struct BuildCmd {
+    /// Watch the sources and rebuild the affected bins on changes
+    #[arg(short, long)]
+    watch: bool,
+    /// Rebuild every bin, even if its inputs have not changed
+    #[arg(short, long)]
+    force: bool,
+    /// Number of bins to build in parallel
+    #[arg(short, long, value_name = "N", default_value_t = num_cpus::get())]
+    jobs: usize,
+}
+
+impl CliCommand for BuildCmd {
+    fn run(&self) -> CliResult {
+        tips::debug("Compiling source and building binaries");
+
+        if self.watch {
+            return watch::watch(&CONFIG.bins());
+        }
+        let manifest_file = CONFIG.build_manifest_file();
+        let mut manifest = BuildManifest::load(&manifest_file);
+        let key = BuildKey {
+            config: calculate_file_sha256(CONFIG.path())?,
+            term_width: get_term_width(),
+            formatter: shell::formatter_version(),
+        };
+        let mut bins = vec![];
+        for bin in CONFIG.bins() {
+            if !self.force && is_up_to_date(&manifest, &bin, &key) {
+                tips::title(&format!("Skipping {} (up to date)", identifier(bin.name())));
+                continue;
+            }
+            // Forget the previous build first, so a failed build is never
+            // considered up to date.
+            manifest.remove(bin.name());
+            bins.push(bin);
+        }
+        save_manifest(&manifest, &manifest_file)?;
+
+        let mut failures = vec![];
+        for (bin, result) in jobs::run(bins, self.jobs, |bin| build(bin, false)) {
+            match result {
+                Ok(sources) => {
+                    manifest.record(bin.name(), &key, &sources, required_tools(&bin)?)?
+                }
+                Err(e) => failures.push((bin.name().to_string(), e)),
+            }
+        }
+        save_manifest(&manifest, &manifest_file)?;
+        jobs::check_failures("build", failures)?;
+
+        Ok(())
+    }
+}
+
+struct BuildFile {
+    inner: fs::File,
+    sourcemap: SourceMap,
+    /// Origin of the lines being written.
+    origin: Option<Mapping>,
+}
+
+impl BuildFile {
+    fn new(fp: fs::File, name: &str) -> Self {
+        Self { inner: fp, sourcemap: SourceMap::new(name), origin: None }
+    }
+
+    fn set_origin(&mut self, origin: Option<Mapping>) {
+        self.origin = origin;
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    fn writeln(&mut self, buf: &str) -> anyhow::Result<()> {
+        for _ in buf.split('\n') {
+            self.sourcemap.push(self.origin);
+        }
+        Ok(writeln!(self.inner, "{buf}")?)
+    }
+
+    fn write_newline(&mut self) -> anyhow::Result<()> {
+        self.sourcemap.push(self.origin);
+        Ok(writeln!(self.inner)?)
+    }
+
+    fn write_meta(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.writeln(&ArgcTag::meta(key, value))?;
+        Ok(())
+    }
+
+    fn write_meta_version(&mut self, version: &str) -> anyhow::Result<()> {
+        self.writeln(&ArgcTag::meta_version(version))?;
+        Ok(())
+    }
+
+    fn write_include_header(&mut self, filename: &str) -> anyhow::Result<()> {
+        self.writeln(&format!("# {filename}"))
+    }
+
+    fn write_argc_hook(&mut self) -> anyhow::Result<()> {
+        self.set_origin(None);
+        self.writeln("\n")?;
+        self.writeln(r#"eval "$(argc --argc-eval "$0" "$@")""#)
+    }
+}
+
+/// Tracks the state of `@include` resolution for a single bin.
+///
+/// Every file is inlined at most once, and including a file that is still
+/// being expanded is reported as a cycle.
+struct IncludeResolver {
+    base_dir: PathBuf,
+    /// Files currently being expanded, outermost first.
+    stack: Vec<(PathBuf, String)>,
+    /// Files already inlined into the output.
+    included: HashSet<PathBuf>,
+}
+
+impl IncludeResolver {
+    fn new<P: AsRef<Path>>(base_dir: P) -> Self {
+        Self { base_dir: base_dir.as_ref().to_path_buf(), stack: vec![], included: HashSet::new() }
+    }
+
+    fn resolve(&self, filename: &str) -> PathBuf {
+        self.base_dir.join(filename)
+    }
+
+    /// Pushes `path` onto the include stack.
+    ///
+    /// Returns `false` if the file has already been inlined and should be
+    /// skipped.
+    fn enter(&mut self, path: &Path, name: &str) -> anyhow::Result<bool> {
+        let key = ensure_path(path)?;
+        if self.stack.iter().any(|(p, _)| p == &key) {
+            let mut chain: Vec<_> = self.stack.iter().map(|(_, n)| n.as_str()).collect();
+            chain.push(name);
+            anyhow::bail!("include cycle detected: {}", chain.join(" -> "));
+        }
+        if !self.included.insert(key.clone()) {
+            return Ok(false);
+        }
+        self.stack.push((key, name.to_string()));
+        Ok(true)
+    }
+
+    fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    /// Consumes the resolver, returning every file that has been inlined.
+    fn into_sources(self) -> HashSet<PathBuf> {
+        self.included
+    }
+}
+
+fn save_manifest(manifest: &BuildManifest, path: &Path) -> anyhow::Result<()> {
+    manifest
+        .save(path)
+        .with_context(|| format!("failed to write build manifest to '{}'", path.display()))
+}
+
+/// Returns `true` if the outputs of `bin` exist and were built from the
+/// current inputs.
+fn is_up_to_date(manifest: &BuildManifest, bin: &Bin, key: &BuildKey) -> bool {
+    let bin_name = bin.name();
+    CONFIG.bin_file(bin_name).is_file()
+        && CONFIG.sourcemap_file(bin_name).is_file()
+        && manifest.is_fresh(bin_name, key)
+}
+
+fn get_term_width() -> Option<usize> {
+    std::env::var("TERM_WIDTH").ok().and_then(|v| v.parse().ok())
+}
+
+fn ensure_path<P: AsRef<Path>>(path: P) -> anyhow::Result<PathBuf> {
+    let p = path.as_ref();
+    p.canonicalize().with_context(|| format!("file not found: {}", p.display()))
+}
+
+fn check_file<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
+    let p = path.as_ref();
+    if !p.exists() {
+        anyhow::bail!("file not found: {p:?}");
+    }
+    Ok(())
+}
+
+/// Builds `bin` and returns the source files it was compiled from.
+///
+/// Progress is not printed when `quiet` is set.
+fn build(bin: &Bin, quiet: bool) -> anyhow::Result<HashSet<PathBuf>> {
+    let progress = |title: &str, detail: String| {
+        if !quiet {
+            tips::h1(title);
+            tips::print(&detail);
+        }
+    };
+    let bin_name = bin.name();
+    if !quiet {
+        tips::title(&format!("Building {}", identifier(bin_name)));
+    }
+
+    let src = bin.path();
+    check_file(src)?;
+    let dst = CONFIG.build_file(&format!("{bin_name}.sh"));
+    let mut bf = BuildFile::new(create_file(&dst)?, bin_name);
+    progress("Compiling source", format!("{} -> {}", src.display(), dst.display()));
+    let mut resolver = IncludeResolver::new(CONFIG.src_dir());
+    let name = src.strip_prefix(CONFIG.src_dir()).unwrap_or(src).display().to_string();
+    resolver.enter(src, &name)?;
+    compile(&mut bf, src, &mut resolver)?;
+    resolver.leave();
+    bf.write_argc_hook()?;
+    bf.flush()?;
+    let compiled = fs::read_to_string(&dst)?;
+    fmt_shell(&dst)?;
+    let source = fs::read_to_string(&dst)?;
+    if CONFIG.shellcheck().enabled() {
+        progress("Running shellcheck", dst.display().to_string());
+        run_shellcheck(&dst, &bf.sourcemap.remap(&compiled, &source))?;
+    }
+
+    // Build scripts without `argc` dependency
+    let target = CONFIG.bin_file(bin_name);
+    progress("Argc building", format!("{} -> {}", dst.display(), target.display()));
+    let content = argc::build(&source, bin_name, get_term_width())?;
+    create_file(&target)?
+        .write_all(content.as_bytes())
+        .with_context(|| format!("failed to write script to '{}'", target.display()))?;
+    fmt_shell(&target)?;
+    set_executable(&target)
+        .with_context(|| format!("failed to set execute permission to '{}'", target.display()))?;
+
+    let sourcemap_file = CONFIG.sourcemap_file(bin_name);
+    progress("Generating source map", sourcemap_file.display().to_string());
+    let output = fs::read_to_string(&target)?;
+    bf.sourcemap
+        .remap(&compiled, &output)
+        .save(&sourcemap_file)
+        .with_context(|| format!("failed to write source map to '{}'", sourcemap_file.display()))?;
+
+    Ok(resolver.into_sources())
+}
+
+/// Returns the tools declared with `@meta require-tools` by the compiled
+/// script of `bin`.
+fn required_tools(bin: &Bin) -> anyhow::Result<BTreeSet<String>> {
+    let source = fs::read_to_string(CONFIG.build_file(&format!("{}.sh", bin.name())))?;
+    let mut tools = BTreeSet::new();
+    for event in parse(&source)? {
+        if let EventData::Meta(key, value) = event.data
+            && key == tag::META_REQUIRE_TOOLS
+        {
+            tools.extend(
+                value.split(',').map(str::trim).filter(|x| !x.is_empty()).map(String::from),
+            );
+        }
+    }
+    Ok(tools)
+}
+
+/// Runs shellcheck on the compiled script at `path` and reports the findings at
+/// their source locations.
+fn run_shellcheck(path: &Path, sourcemap: &SourceMap) -> anyhow::Result<()> {
+    let deny_warnings = CONFIG.shellcheck().deny_warnings();
+    let mut failures = 0;
+    for comment in shellcheck(path)? {
+        let (file, line) = match sourcemap.lookup(comment.line) {
+            Some(location) => (location.source.to_string(), location.line),
+            None => (path.display().to_string(), comment.line),
+        };
+        let severity = if comment.level == "error" || deny_warnings {
+            failures += 1;
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        let message = format!("SC{}: {}", comment.code, comment.message);
+        tips::print(&Diagnostic { file, line, severity, message }.to_string());
+    }
+    if failures > 0 {
+        anyhow::bail!("shellcheck reported {failures} problem(s)");
+    }
+    Ok(())
+}
+
+/// Writes the compiled content of `path` to `bf`, expanding `@include`
+/// directives recursively.
+fn compile<P: AsRef<Path>>(
+    bf: &mut BuildFile,
+    path: P,
+    resolver: &mut IncludeResolver,
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to load script at '{}'", path.display()))?;
+    let events = parse(&source).with_context(|| format!("failed to parse '{}'", path.display()))?;
+    let source_name = path.strip_prefix(CONFIG.root()).unwrap_or(path).display().to_string();
+    let source_idx = bf.sourcemap.add_source(&source_name);
+    for event in &events {
+        bf.set_origin(Some((source_idx, event.position)));
+        match &event.data {
+            EventData::Include(filename) => {
+                let filepath = resolver.resolve(filename);
+                check_file(&filepath).with_context(|| {
+                    format!("failed to include '{filename}' from '{}'", path.display())
+                })?;
+                if resolver.enter(&filepath, filename)? {
+                    log::debug!("write include file: {filename}");
+                    bf.write_include_header(filename)?;
+                    compile(bf, &filepath, resolver)?;
+                    bf.set_origin(Some((source_idx, event.position)));
+                    bf.write_newline()?;
+                    resolver.leave();
+                } else {
+                    log::debug!("skip already included file: {filename}");
+                }
+            }
+            EventData::Meta(key, value) => {
+                if ArgcTag::is_meta_version(key) {
+                    bf.write_meta_version(CONFIG.project().version())?;
+                } else {
+                    bf.write_meta(key, value)?;
+                }
+            }
+            EventData::Unknown(value) => {
+                bf.writeln(value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_temp_dir(name: &str, files: &[&str]) -> PathBuf {
+        let temp_dir = std::env::temp_dir().join(name);
+        if temp_dir.exists() {
+            fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        for file in files {
+            create_file(temp_dir.join(file)).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_include_resolver_skips_included_file() {
+        let temp_dir = setup_temp_dir("test_include_resolver_skips", &["main.sh", "a.sh"]);
+        let mut resolver = IncludeResolver::new(&temp_dir);
+        assert!(resolver.enter(&resolver.resolve("main.sh"), "main.sh").unwrap());
+        assert!(resolver.enter(&resolver.resolve("a.sh"), "a.sh").unwrap());
+        resolver.leave();
+        assert!(!resolver.enter(&resolver.resolve("a.sh"), "a.sh").unwrap());
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_resolver_detects_cycle() {
+        let temp_dir = setup_temp_dir("test_include_resolver_cycle", &["main.sh", "a.sh", "b.sh"]);
+        let mut resolver = IncludeResolver::new(&temp_dir);
+        resolver.enter(&resolver.resolve("main.sh"), "main.sh").unwrap();
+        resolver.enter(&resolver.resolve("a.sh"), "a.sh").unwrap();
+        resolver.enter(&resolver.resolve("b.sh"), "b.sh").unwrap();
+        let err = resolver.enter(&resolver.resolve("a.sh"), "a.sh").unwrap_err();
+        assert_eq!(err.to_string(), "include cycle detected: main.sh -> a.sh -> b.sh -> a.sh");
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::{RecursiveMode, Watcher};
+
+use super::build;
+use crate::config::Bin;
+use crate::prelude::*;
+use crate::utils::formatter::{Formatter, identifier};
+use crate::utils::tips;
+
+/// How long to wait for a burst of file events to settle before rebuilding.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Builds `bins`, then rebuilds the affected ones whenever their sources
+/// change.
+///
+/// Build errors are reported and the watcher keeps running.
+pub fn watch(bins: &[Bin]) -> CliResult {
+    // Sources of each bin, or `None` if its last build failed.
+    let mut sources: Vec<Option<HashSet<PathBuf>>> = bins
+        .iter()
+        .map(|bin| build(bin, false).map_err(|e| tips::error(&format!("{e:#}"))).ok())
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let src_dir = CONFIG.src_dir();
+    let src_dir = src_dir.canonicalize().unwrap_or(src_dir);
+    let mut watched = HashSet::new();
+    if src_dir.is_dir() {
+        watcher.watch(&src_dir, RecursiveMode::Recursive)?;
+    }
+    let outside: Vec<_> = bins.iter().map(|bin| bin.path().to_path_buf()).collect();
+    watch_files(&mut watcher, &src_dir, &mut watched, outside.iter());
+    watch_files(&mut watcher, &src_dir, &mut watched, sources.iter().flatten().flatten());
+
+    tips::h1(&format!("Watching {} for changes", src_dir.display()));
+    while let Some(changed) = wait_changes(&rx) {
+        for (bin, bin_sources) in bins.iter().zip(sources.iter_mut()) {
+            let affected = match bin_sources {
+                Some(files) => files.iter().any(|f| changed.contains(f)),
+                None => true,
+            };
+            if !affected {
+                continue;
+            }
+            *bin_sources = rebuild(bin);
+            if let Some(files) = bin_sources {
+                watch_files(&mut watcher, &src_dir, &mut watched, files.iter());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Blocks until files change, then collects the paths changed until events
+/// settle for [`DEBOUNCE_TIMEOUT`].
+///
+/// Returns `None` when the watcher has stopped.
+fn wait_changes(rx: &mpsc::Receiver<notify::Result<notify::Event>>) -> Option<HashSet<PathBuf>> {
+    let mut changed = HashSet::new();
+    let mut result = rx.recv().ok()?;
+    loop {
+        match result {
+            // Reading the sources while building must not trigger a rebuild.
+            Ok(event) if !event.kind.is_access() => {
+                changed.extend(event.paths.into_iter().map(|p| p.canonicalize().unwrap_or(p)));
+            }
+            Ok(_) => {}
+            Err(e) => tips::error(&format!("watch error: {e}")),
+        }
+        result = match rx.recv_timeout(DEBOUNCE_TIMEOUT) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) if changed.is_empty() => rx.recv().ok()?,
+            Err(mpsc::RecvTimeoutError::Timeout) => return Some(changed),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+        };
+    }
+}
+
+/// Rebuilds `bin`, printing a single status line.
+fn rebuild(bin: &Bin) -> Option<HashSet<PathBuf>> {
+    let start = Instant::now();
+    let time = chrono::Local::now().format("%H:%M:%S");
+    match build(bin, true) {
+        Ok(files) => {
+            let elapsed = start.elapsed().as_millis();
+            let message = format!("{} in {elapsed}ms", identifier(bin.name()));
+            println!("[{time}] {}", Formatter::new().success("Rebuilt").text(&message));
+            Some(files)
+        }
+        Err(e) => {
+            let message = format!("{} {e:#}", identifier(bin.name()));
+            println!("[{time}] {}", Formatter::new().error("Failed").text(&message));
+            None
+        }
+    }
+}
+
+/// Watches the files that are not already covered by watching `src_dir`.
+fn watch_files<'a, I>(
+    watcher: &mut dyn Watcher,
+    src_dir: &Path,
+    watched: &mut HashSet<PathBuf>,
+    files: I,
+) where
+    I: Iterator<Item = &'a PathBuf>,
+{
+    for file in files {
+        if file.starts_with(src_dir) || !watched.insert(file.clone()) {
+            continue;
+        }
+        if let Err(e) = watcher.watch(file, RecursiveMode::NonRecursive) {
+            tips::warning(&format!("failed to watch '{}': {e}", file.display()));
+        }
+    }
+}
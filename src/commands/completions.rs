@@ -1,5 +1,5 @@
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::Context;
@@ -11,21 +11,24 @@ use crate::config::Bin;
 use crate::prelude::*;
 use crate::utils::formatter::identifier;
 use crate::utils::fs::create_file;
-use crate::utils::tips;
+use crate::utils::{jobs, tips};
 
 /// Generate shell completion scripts.
 #[derive(clap::Parser, Debug)]
-pub struct CompletionsCmd {}
+pub struct CompletionsCmd {
+    /// Number of bins to generate completions for in parallel
+    #[arg(short, long, value_name = "N", default_value_t = num_cpus::get())]
+    jobs: usize,
+}
 
 impl CliCommand for CompletionsCmd {
     fn run(&self) -> CliResult {
-        for bin in &CONFIG.bins() {
-            for shell in Shell::value_variants() {
-                gen_completions(bin, shell)?;
-            }
-        }
-
-        Ok(())
+        let results = jobs::run(CONFIG.bins(), self.jobs, |bin| {
+            Shell::value_variants().iter().try_for_each(|shell| gen_completions(bin, shell))
+        });
+        let failures =
+            results.into_iter().filter_map(|(bin, r)| Some((bin.name().to_string(), r.err()?)));
+        jobs::check_failures("generate completions for", failures)
     }
 }
 
@@ -33,17 +36,27 @@ fn starts_with_compdef(s: &str) -> bool {
     s.lines().next().is_some_and(|line| line.starts_with("#compdef "))
 }
 
-fn gen_completions(bin: &Bin, shell: &Shell) -> anyhow::Result<()> {
-    let bin_name = bin.name();
+/// Generates the completion script of `bin_name` for `shell`.
+pub fn render_completions(bin_name: &str, shell: &Shell) -> anyhow::Result<String> {
     let argc_shell = argc::Shell::from_str(&shell.to_string())?;
     let commands = vec!["argc".to_string(), bin_name.to_string()];
     let mut content = argc::generate_completions(argc_shell, &commands);
     if shell == &Shell::Zsh && !starts_with_compdef(&content) {
         content = format!("#compdef {}\n\n{content}", commands.join(" "));
     };
-    let comp_file = CONFIG.comp_file(
-        Path::new(shell.to_possible_value().unwrap().get_name()).join(shell.file_name(bin_name)),
-    );
+    Ok(content)
+}
+
+/// Returns the path of the completion script relative to the completions
+/// directory.
+pub fn completions_path(bin_name: &str, shell: &Shell) -> PathBuf {
+    Path::new(shell.to_possible_value().unwrap().get_name()).join(shell.file_name(bin_name))
+}
+
+fn gen_completions(bin: &Bin, shell: &Shell) -> anyhow::Result<()> {
+    let bin_name = bin.name();
+    let content = render_completions(bin_name, shell)?;
+    let comp_file = CONFIG.comp_file(completions_path(bin_name, shell));
     tips::h1(&format!(
         "Generating {} completions for {}",
         identifier(&shell.to_string()),
@@ -52,6 +65,6 @@ fn gen_completions(bin: &Bin, shell: &Shell) -> anyhow::Result<()> {
     create_file(&comp_file)?
         .write_all(content.as_bytes())
         .with_context(|| format!("failed to write script to '{}'", comp_file.display()))?;
-    println!("{}", comp_file.display());
+    tips::print(&comp_file.display().to_string());
     Ok(())
 }
@@ -1,10 +1,11 @@
 use std::fs;
 use std::io::Write;
 
-use crate::config::DEFAULT_CONFIG;
+use crate::config::DEFAULT_CONFIG_NAME;
 use crate::prelude::*;
 use crate::utils::fs::create_file;
 use crate::utils::path::add_extension;
+use crate::utils::template;
 use crate::utils::tips;
 
 /// Generate the configuration files.
@@ -25,7 +26,8 @@ impl CliCommand for ConfigGenerateCmd {
                 tips::h1("Backing up");
                 println!("{} -> {}", config_path.display(), backup_path.display());
             }
-            create_file(&config_path)?.write_all(DEFAULT_CONFIG.as_bytes())?;
+            let content = template::load(CONFIG.root(), DEFAULT_CONFIG_NAME)?;
+            create_file(&config_path)?.write_all(content.as_bytes())?;
             tips::h1("Generating");
             println!("{}", config_path.display());
         } else {
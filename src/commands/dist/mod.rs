@@ -0,0 +1,243 @@
+mod sign;
+mod verify;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use walkdir::WalkDir;
+
+use crate::prelude::*;
+use crate::utils::archive::{ArchiveEntry, ArchiveFormat, create_archive};
+use crate::utils::formatter::Formatter;
+use crate::utils::fs::create_file;
+use crate::utils::hashlib::{
+    HashAlgorithm, calculate_file_hash, calculate_file_sha256, format_checksum_line,
+    parse_checksum_line,
+};
+use crate::utils::include::resolve_includes;
+use crate::utils::tips;
+use crate::utils::vcs::git;
+
+/// Creates a distribution archive.
+#[derive(clap::Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct DistCmd {
+    #[command(subcommand)]
+    command: Option<DistSubcommand>,
+    /// Archive formats to create, defaults to `[dist].formats`
+    #[arg(short, long = "format", value_name = "FORMAT", value_delimiter = ',')]
+    formats: Vec<ArchiveFormat>,
+    /// Create every archive twice and fail if the copies differ
+    #[arg(long)]
+    verify_reproducible: bool,
+}
+
+#[derive(clap::Subcommand, shinc_derive::CliCommand, Debug)]
+enum DistSubcommand {
+    Verify(verify::DistVerifyCmd),
+}
+
+impl CliCommand for DistCmd {
+    fn run(&self) -> CliResult {
+        if let Some(command) = &self.command {
+            return command.run();
+        }
+        tips::debug("Distributing binaries");
+
+        let mut formats = if self.formats.is_empty() {
+            CONFIG.dist_formats().to_vec()
+        } else {
+            self.formats.clone()
+        };
+        formats.sort();
+        formats.dedup();
+        if formats.is_empty() {
+            anyhow::bail!("no archive format specified in `[dist].formats` or `--format`");
+        }
+
+        let mut entries = collect_entries(&[CONFIG.bin_dir(), CONFIG.share_dir()])?;
+        entries.extend(resolve_includes(CONFIG.root(), &CONFIG.dist_include_extra()?)?);
+        check_conflicts(&entries)?;
+        if let Some(prefix) = CONFIG.dist_prefix()? {
+            entries.iter_mut().for_each(|x| x.path = prefix.join(&x.path));
+        }
+        let mtime = source_date_epoch()?;
+        for format in formats {
+            let filename = CONFIG.dist_archive_name(format);
+            let output = CONFIG.dist_file(&filename);
+            tips::h1("Archiving files");
+            println!("{}", output.display());
+            create_archive(&output, format, &entries, mtime)?;
+
+            let checksum = calculate_file_sha256(&output)?;
+            if self.verify_reproducible {
+                verify_reproducible(&output, format, &entries, mtime, &checksum)?;
+            }
+            let checksum_file = CONFIG.dist_file(&format!("{filename}.sha256"));
+            tips::h1("Generating sha256sum");
+            println!("{}", checksum_file.display());
+            let mut fp = create_file(&checksum_file)?;
+            writeln!(fp, "{}", format_checksum_line(&checksum, &filename))?;
+        }
+
+        let dir = CONFIG.dist_dir();
+        write_manifests(&dir, CONFIG.dist_checksums())?;
+        if let Some(settings) = CONFIG.dist_sign() {
+            sign::sign_files(settings, &signed_files(&dir)?, mtime)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the sha256 of the archive in `format`, as written by `shinc dist`.
+pub fn archive_checksum(format: ArchiveFormat) -> anyhow::Result<String> {
+    let path = CONFIG.dist_file(&format!("{}.sha256", CONFIG.dist_archive_name(format)));
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read '{}', run `shinc dist` first", path.display()))?;
+    match content.lines().next().and_then(parse_checksum_line) {
+        Some((checksum, _)) => Ok(checksum.to_string()),
+        None => anyhow::bail!("invalid checksum file '{}'", path.display()),
+    }
+}
+
+/// Returns the files of `dir` that are signed: the artifacts and the checksum
+/// manifests.
+fn signed_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files: Vec<_> = list_artifacts(dir)?.iter().map(|x| dir.join(x)).collect();
+    for algorithm in HashAlgorithm::value_variants() {
+        let manifest = dir.join(algorithm.manifest_name());
+        if manifest.is_file() {
+            files.push(manifest);
+        }
+    }
+    Ok(files)
+}
+
+/// Writes a checksum manifest for each of `algorithms`, covering every
+/// artifact in `dir`.
+fn write_manifests(dir: &Path, algorithms: &[HashAlgorithm]) -> anyhow::Result<()> {
+    let artifacts = list_artifacts(dir)?;
+    for algorithm in algorithms {
+        let manifest = dir.join(algorithm.manifest_name());
+        tips::h1(&format!("Generating {}", algorithm.manifest_name()));
+        println!("{}", manifest.display());
+        let mut fp = create_file(&manifest)?;
+        for filename in &artifacts {
+            let checksum = calculate_file_hash(dir.join(filename), *algorithm)?;
+            writeln!(fp, "{}", format_checksum_line(&checksum, filename))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the sorted filenames of the artifacts in `dir`, leaving out the
+/// checksum and signature files.
+fn list_artifacts(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let manifests: Vec<_> =
+        HashAlgorithm::value_variants().iter().map(|x| x.manifest_name()).collect();
+    let extensions = [".sha256", ".minisig", ".sig"];
+    let mut artifacts = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let filename = entry.file_name().to_string_lossy().into_owned();
+        if manifests.contains(&filename.as_str())
+            || extensions.iter().any(|x| filename.ends_with(x))
+        {
+            continue;
+        }
+        artifacts.push(filename);
+    }
+    artifacts.sort();
+    Ok(artifacts)
+}
+
+/// Returns the mtime of the archived files: `SOURCE_DATE_EPOCH` if set, or the
+/// time of the last commit.
+pub fn source_date_epoch() -> anyhow::Result<u64> {
+    if let Ok(value) = std::env::var("SOURCE_DATE_EPOCH") {
+        return value.trim().parse().with_context(|| format!("invalid SOURCE_DATE_EPOCH: {value}"));
+    }
+    match git::Repo::with_local().and_then(|repo| repo.head_commit_time()) {
+        Ok(time) => Ok(time.max(0) as u64),
+        Err(e) => {
+            tips::warning(&format!("failed to get the last commit time, using 0: {e}"));
+            Ok(0)
+        }
+    }
+}
+
+/// Creates the archive again next to `output` and checks that both copies
+/// have the same `checksum`.
+fn verify_reproducible(
+    output: &Path,
+    format: ArchiveFormat,
+    entries: &[ArchiveEntry],
+    mtime: u64,
+    checksum: &str,
+) -> anyhow::Result<()> {
+    tips::h1("Verifying reproducibility");
+    let mut copy = output.as_os_str().to_owned();
+    copy.push(".verify");
+    let copy = PathBuf::from(copy);
+    create_archive(&copy, format, entries, mtime)?;
+    let copy_checksum = calculate_file_sha256(&copy);
+    fs::remove_file(&copy)?;
+    let copy_checksum = copy_checksum?;
+    if copy_checksum != checksum {
+        anyhow::bail!(
+            "archive is not reproducible: '{}' has sha256 {checksum} then {copy_checksum}",
+            output.display()
+        );
+    }
+    println!("{}", Formatter::new().success("Reproducible").text(checksum));
+    Ok(())
+}
+
+/// Fails if an extra file would overwrite a built file in the archive.
+fn check_conflicts(entries: &[ArchiveEntry]) -> anyhow::Result<()> {
+    let mut paths = HashSet::new();
+    for entry in entries {
+        if !paths.insert(&entry.path) {
+            anyhow::bail!(
+                "'{}' is placed at '{}', which is already in the archive",
+                entry.source.display(),
+                entry.path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Lists the built files to archive, directories are added recursively.
+fn collect_entries<S: AsRef<Path>>(sources: &[S]) -> anyhow::Result<Vec<ArchiveEntry>> {
+    let mut entries = vec![];
+    for src in sources {
+        let path = src.as_ref();
+        if !path.exists() {
+            log::warn!("{} not found, skipping.", path.display());
+            continue;
+        }
+        let base = path.strip_prefix(CONFIG.target_dir()).unwrap_or(path);
+        for entry in WalkDir::new(path).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(path)?;
+            let archive_path = if relative.as_os_str().is_empty() {
+                base.to_path_buf()
+            } else {
+                base.join(relative)
+            };
+            entries.push(ArchiveEntry { source: entry.into_path(), path: archive_path });
+        }
+    }
+    Ok(entries)
+}
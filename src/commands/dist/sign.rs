@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::config::Sign;
+use crate::prelude::*;
+use crate::utils::path::add_extension;
+use crate::utils::sign::{MinisignKey, SignMode, ssh_sign, ssh_verify, verify_minisign};
+use crate::utils::tips;
+
+/// The environment variable holding the password of an encrypted minisign
+/// key, it is prompted for when unset.
+const PASSWORD_ENV: &str = "SHINC_SIGN_PASSWORD";
+
+/// Signs `files` with the `[dist.sign]` key, each signature is written next to
+/// its file. `mtime` is recorded in the trusted comment of minisign
+/// signatures, so that they are reproducible.
+pub fn sign_files(sign: &Sign, files: &[PathBuf], mtime: u64) -> anyhow::Result<()> {
+    tips::h1(&format!("Signing with {}", sign.mode().as_str()));
+    match sign.mode() {
+        SignMode::Minisign => {
+            let key = MinisignKey::decode(&read_secret_key(sign)?, read_password)?;
+            if let Some(public_key) = sign.public_key().filter(|x| !x.trim().contains('\n'))
+                && public_key.trim() != key.public_key()
+            {
+                anyhow::bail!(
+                    "the secret key does not match `[dist.sign].public_key`, its public key is {}",
+                    key.public_key()
+                );
+            }
+            for file in files {
+                let filename = file.file_name().unwrap_or_default().to_string_lossy();
+                let comment = format!("timestamp:{mtime}\tfile:{filename}\thashed");
+                let signature = add_extension(file, sign.mode().extension())?;
+                fs::write(&signature, key.sign(&fs::read(file)?, &comment))?;
+                println!("{}", signature.display());
+            }
+        }
+        SignMode::Ssh => {
+            let key = ssh_key_file(sign)?;
+            for file in files {
+                ssh_sign(file, key.path())?;
+                println!("{}", add_extension(file, sign.mode().extension())?.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the signature of `path` with `[dist.sign].public_key`.
+pub fn verify_file(sign: &Sign, path: &Path) -> anyhow::Result<()> {
+    let Some(public_key) = sign.public_key() else {
+        anyhow::bail!("`[dist.sign].public_key` is required to verify signatures");
+    };
+    let signature = add_extension(path, sign.mode().extension())?;
+    if !signature.is_file() {
+        anyhow::bail!("signature not found");
+    }
+    match sign.mode() {
+        SignMode::Minisign => {
+            verify_minisign(public_key, &fs::read(path)?, &fs::read_to_string(&signature)?)
+        }
+        SignMode::Ssh => ssh_verify(path, &signature, &CONFIG.inferred_dist_name(), public_key),
+    }
+}
+
+/// Returns the content of the secret key, read from `[dist.sign].key_env`
+/// if set, or from `[dist.sign].key`.
+fn read_secret_key(sign: &Sign) -> anyhow::Result<String> {
+    if let Some(value) = sign.key_env().and_then(|name| std::env::var(name).ok()) {
+        return Ok(value);
+    }
+    let Some(key) = sign.key() else {
+        anyhow::bail!("no secret key, set `[dist.sign].key` or `[dist.sign].key_env`");
+    };
+    fs::read_to_string(key)
+        .with_context(|| format!("failed to read the secret key at '{}'", key.display()))
+}
+
+fn read_password() -> anyhow::Result<String> {
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(password);
+    }
+    Ok(dialoguer::Password::new().with_prompt("Password of the minisign key").interact()?)
+}
+
+/// The SSH key to pass to `ssh-keygen`, a copy of the key is made when it
+/// comes from an environment variable.
+enum SshKey<'a> {
+    File(&'a Path),
+    /// A copy in a private directory, removed with it when dropped.
+    Temporary {
+        path: PathBuf,
+        _dir: tempfile::TempDir,
+    },
+}
+
+impl SshKey<'_> {
+    fn path(&self) -> &Path {
+        match self {
+            Self::File(path) => path,
+            Self::Temporary { path, .. } => path,
+        }
+    }
+}
+
+fn ssh_key_file(sign: &Sign) -> anyhow::Result<SshKey<'_>> {
+    let Some(mut value) = sign.key_env().and_then(|name| std::env::var(name).ok()) else {
+        return match sign.key() {
+            Some(key) => Ok(SshKey::File(key)),
+            None => anyhow::bail!("no secret key, set `[dist.sign].key` or `[dist.sign].key_env`"),
+        };
+    };
+    if !value.ends_with('\n') {
+        value.push('\n');
+    }
+    // The directory is only accessible by the current user, and the key is
+    // created there, never opened through an existing file or symlink.
+    let dir = tempfile::TempDir::with_prefix("shinc-sign-")?;
+    let path = dir.path().join("key");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // ssh-keygen refuses keys readable by others.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut fp = options.open(&path)?;
+    std::io::Write::write_all(&mut fp, value.as_bytes())?;
+    Ok(SshKey::Temporary { path, _dir: dir })
+}
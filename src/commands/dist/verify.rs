@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::ValueEnum;
+
+use super::{list_artifacts, sign, signed_files};
+use crate::prelude::*;
+use crate::utils::formatter::Formatter;
+use crate::utils::hashlib::{HashAlgorithm, calculate_file_hash, parse_checksum_line};
+use crate::utils::tips;
+
+/// Check the distribution archives against the checksum manifests and
+/// signatures.
+#[derive(clap::Parser, Debug)]
+pub struct DistVerifyCmd {
+    /// Directory of the archives, defaults to `target/dist`
+    dir: Option<PathBuf>,
+}
+
+impl CliCommand for DistVerifyCmd {
+    fn run(&self) -> CliResult {
+        let dir = self.dir.clone().unwrap_or_else(|| CONFIG.dist_dir());
+        tips::debug(&format!("Verifying {}", dir.display()));
+
+        let mut checked = 0;
+        let mut failures = vec![];
+        for algorithm in HashAlgorithm::value_variants() {
+            let manifest = dir.join(algorithm.manifest_name());
+            if !manifest.is_file() {
+                continue;
+            }
+            tips::h1(&format!("Checking {}", algorithm.manifest_name()));
+            let content = fs::read_to_string(&manifest)
+                .with_context(|| format!("failed to read '{}'", manifest.display()))?;
+            let mut listed = vec![];
+            for (i, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let Some((expected, filename)) = parse_checksum_line(line) else {
+                    anyhow::bail!("invalid line {} in '{}': {line}", i + 1, manifest.display());
+                };
+                listed.push(filename.to_string());
+                let path = dir.join(filename);
+                let result = if path.is_file() {
+                    match calculate_file_hash(&path, *algorithm)? {
+                        actual if actual == expected => Ok(()),
+                        _ => Err(anyhow::format_err!("{} mismatch", algorithm.as_str())),
+                    }
+                } else {
+                    Err(anyhow::format_err!("file not found"))
+                };
+                report(filename, result, &mut failures);
+            }
+            for filename in list_artifacts(&dir)? {
+                if !listed.contains(&filename) {
+                    let reason = format!("not listed in {}", algorithm.manifest_name());
+                    report(&filename, Err(anyhow::format_err!(reason)), &mut failures);
+                }
+            }
+            checked += 1;
+        }
+        if checked == 0 {
+            anyhow::bail!("no checksum manifest found in '{}'", dir.display());
+        }
+
+        if let Some(settings) = CONFIG.dist_sign() {
+            tips::h1("Checking signatures");
+            for path in signed_files(&dir)? {
+                let filename = path.file_name().unwrap_or_default().to_string_lossy();
+                report(&filename, sign::verify_file(settings, &path), &mut failures);
+            }
+        }
+
+        if !failures.is_empty() {
+            anyhow::bail!("{} check(s) failed:\n  {}", failures.len(), failures.join("\n  "));
+        }
+        Ok(())
+    }
+}
+
+fn report(filename: &str, result: anyhow::Result<()>, failures: &mut Vec<String>) {
+    match result {
+        Ok(()) => println!("{}", Formatter::new().success("OK").text(filename)),
+        Err(e) => {
+            println!("{}", Formatter::new().error("FAILED").text(filename));
+            failures.push(format!("{filename}: {e:#}"));
+        }
+    }
+}
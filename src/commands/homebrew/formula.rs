@@ -3,14 +3,12 @@ use std::path::PathBuf;
 
 use heck::ToPascalCase;
 
-use crate::include_template;
 use crate::prelude::*;
 use crate::utils::fs::create_file;
 use crate::utils::hashlib::calculate_url_sha256;
+use crate::utils::template;
 
 const FORMULA_NAME: &str = "homebrew/formula.rb";
-const FORMULA_TEMPLATE: &str = include_template!("homebrew/formula.rb");
-const TEMPLATES: [(&str, &str); 1] = [(FORMULA_NAME, FORMULA_TEMPLATE)];
 
 /// Generate Homebrew formulae.
 #[derive(clap::Parser, Debug)]
@@ -56,7 +54,7 @@ impl HomebrewFormulaCmd {
     }
 
     fn render_formula(&self, name: &str, ctx: &mut tera::Context) -> anyhow::Result<()> {
-        let engine = init_engine()?;
+        let engine = template::engine(CONFIG.root(), &[FORMULA_NAME])?;
         if let Some(filepath) = self.formula_file(name) {
             let fp = create_file(filepath)?;
             engine.render_to(FORMULA_NAME, ctx, fp)?;
@@ -84,9 +82,3 @@ impl CliCommand for HomebrewFormulaCmd {
         Ok(())
     }
 }
-
-fn init_engine() -> anyhow::Result<tera::Tera> {
-    let mut engine = tera::Tera::default();
-    engine.add_raw_templates(TEMPLATES)?;
-    Ok(engine)
-}
@@ -0,0 +1,16 @@
+use super::new::{ProjectArgs, scaffold};
+use crate::prelude::*;
+
+/// Create a new shinc project in the current directory.
+#[derive(clap::Parser, Debug)]
+pub struct InitCmd {
+    #[command(flatten)]
+    project: ProjectArgs,
+}
+
+impl CliCommand for InitCmd {
+    fn run(&self) -> CliResult {
+        let root = std::env::current_dir()?;
+        scaffold(&root, &self.project)
+    }
+}
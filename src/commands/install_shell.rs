@@ -2,14 +2,13 @@ use std::io::Write;
 
 use anyhow::Context;
 
-use crate::include_template;
 use crate::prelude::*;
 use crate::utils::fs::{create_file, set_executable};
 use crate::utils::shell::fmt_shell;
+use crate::utils::sign::{SignMode, minisign_key_line};
+use crate::utils::template;
 
 const INSTALL_NAME: &str = "shell/install.sh";
-const INSTALL_TEMPLATE: &str = include_template!("shell/install.sh");
-const TEMPLATES: [(&str, &str); 1] = [(INSTALL_NAME, INSTALL_TEMPLATE)];
 
 /// Generate the install shell script for the project.
 #[derive(clap::Parser, Debug)]
@@ -22,14 +21,6 @@ pub struct InstallShellCmd {
     pub raw: bool,
 }
 
-impl InstallShellCmd {
-    fn init_engine(&self) -> anyhow::Result<tera::Tera> {
-        let mut engine = tera::Tera::default();
-        engine.add_raw_templates(TEMPLATES)?;
-        Ok(engine)
-    }
-}
-
 impl CliCommand for InstallShellCmd {
     fn run(&self) -> CliResult {
         let name = CONFIG.inferred_dist_name();
@@ -41,7 +32,17 @@ impl CliCommand for InstallShellCmd {
         let mut ctx = tera::Context::new();
         ctx.insert("name", &name);
         ctx.insert("url", url);
-        let engine = self.init_engine()?;
+        let sign = CONFIG.dist_sign();
+        ctx.insert("sign_mode", sign.map(|x| x.mode().as_str()).unwrap_or_default());
+        // The script passes minisign the key with `-P`, which takes the base64
+        // line only.
+        let public_key = sign.and_then(|x| x.public_key()).unwrap_or_default();
+        let public_key = match sign.map(|x| x.mode()) {
+            Some(SignMode::Minisign) => minisign_key_line(public_key),
+            _ => public_key.trim(),
+        };
+        ctx.insert("public_key", public_key);
+        let engine = template::engine(CONFIG.root(), &[INSTALL_NAME])?;
         let current_dir = std::env::current_dir()?;
         let target = current_dir.join(&self.filename);
         let fp = create_file(&target)?;
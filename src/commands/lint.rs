@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::config::Bin;
+use crate::prelude::*;
+use crate::utils::argc::lint::{Diagnostic, Linter, Severity};
+use crate::utils::argc::parser::{EventData, parse};
+use crate::utils::formatter::identifier;
+use crate::utils::tips;
+
+/// Check the argc annotations of the scripts.
+#[derive(clap::Parser, Debug)]
+pub struct LintCmd {}
+
+impl CliCommand for LintCmd {
+    fn run(&self) -> CliResult {
+        tips::debug("Linting scripts");
+
+        // Files shared by several bins are reported once.
+        let mut seen = HashSet::new();
+        let mut diagnostics = vec![];
+        for bin in &CONFIG.bins() {
+            tips::title(&format!("Linting {}", identifier(bin.name())));
+            for diagnostic in lint_bin(bin)? {
+                if seen.insert(diagnostic.clone()) {
+                    println!("{diagnostic}");
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+
+        let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+        let warnings = diagnostics.len() - errors;
+        println!("\nlint result: {errors} error(s); {warnings} warning(s)");
+        if errors > 0 {
+            anyhow::bail!("{errors} lint error(s) found");
+        }
+        Ok(())
+    }
+}
+
+fn lint_bin(bin: &Bin) -> anyhow::Result<Vec<Diagnostic>> {
+    let mut linter = Linter::new();
+    let mut visited = HashSet::new();
+    let entry = bin.path();
+    if !entry.is_file() {
+        anyhow::bail!("file not found: {}", entry.display());
+    }
+    lint_file(&mut linter, entry, &mut visited)?;
+    Ok(linter.finish(&display_name(entry)))
+}
+
+/// Feeds the events of `path` to `linter`, following `@include` directives.
+fn lint_file(
+    linter: &mut Linter,
+    path: &Path,
+    visited: &mut HashSet<PathBuf>,
+) -> anyhow::Result<()> {
+    if !visited.insert(path.canonicalize()?) {
+        return Ok(());
+    }
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to load script at '{}'", path.display()))?;
+    let events = parse(&source).with_context(|| format!("failed to parse '{}'", path.display()))?;
+    let name = display_name(path);
+    for event in &events {
+        linter.check(&name, event);
+        if let EventData::Include(filename) = &event.data {
+            let filepath = CONFIG.resolve_src_path(filename);
+            if filepath.is_file() {
+                lint_file(linter, &filepath, visited)?;
+            } else {
+                let message = format!("included file '{filename}' not found");
+                linter.report(&name, event.position, Severity::Error, message);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn display_name(path: &Path) -> String {
+    path.strip_prefix(CONFIG.root()).unwrap_or(path).display().to_string()
+}
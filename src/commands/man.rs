@@ -5,18 +5,22 @@ use anyhow::Context;
 use crate::config::Bin;
 use crate::prelude::*;
 use crate::utils::fs::ensure_dir_all;
+use crate::utils::{jobs, tips};
 
 /// Generate man pages.
 #[derive(clap::Parser, Debug)]
-pub struct ManCmd {}
+pub struct ManCmd {
+    /// Number of bins to generate man pages for in parallel
+    #[arg(short, long, value_name = "N", default_value_t = num_cpus::get())]
+    jobs: usize,
+}
 
 impl CliCommand for ManCmd {
     fn run(&self) -> CliResult {
-        for bin in &CONFIG.bins() {
-            gen_man(bin)?;
-        }
-
-        Ok(())
+        let results = jobs::run(CONFIG.bins(), self.jobs, gen_man);
+        let failures =
+            results.into_iter().filter_map(|(bin, r)| Some((bin.name().to_string(), r.err()?)));
+        jobs::check_failures("generate man pages for", failures)
     }
 }
 
@@ -34,7 +38,7 @@ fn gen_man(bin: &Bin) -> anyhow::Result<()> {
         let man_file = CONFIG.man_file(&filename);
         fs::write(&man_file, page)
             .with_context(|| format!("failed to write '{}'", man_file.display()))?;
-        println!("{}", man_file.display());
+        tips::print(&man_file.display().to_string());
     }
     Ok(())
 }
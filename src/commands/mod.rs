@@ -4,9 +4,19 @@ mod completions;
 mod config;
 mod dist;
 mod homebrew;
+mod init;
 mod install_shell;
+mod lint;
 mod man;
+mod new;
+mod nix;
+mod package;
+mod pkgbuild;
 mod release;
+mod snapshot;
+mod template;
+mod test;
+mod trace;
 
 use crate::prelude::*;
 
@@ -21,7 +31,19 @@ pub enum Command {
     Dist(dist::DistCmd),
     #[command(subcommand)]
     Homebrew(homebrew::HomebrewCmd),
+    Init(init::InitCmd),
     InstallShell(install_shell::InstallShellCmd),
+    Lint(lint::LintCmd),
     Man(man::ManCmd),
+    New(new::NewCmd),
+    Nix(nix::NixCmd),
+    #[command(subcommand)]
+    Package(package::PackageCmd),
+    Pkgbuild(pkgbuild::PkgbuildCmd),
     Release(release::ReleaseCmd),
+    Snapshot(snapshot::SnapshotCmd),
+    #[command(subcommand)]
+    Template(template::TemplateCmd),
+    Test(test::TestCmd),
+    Trace(trace::TraceCmd),
 }
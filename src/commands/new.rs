@@ -0,0 +1,176 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::Datelike;
+
+use crate::config::{Config, DEFAULT_CONFIG_NAME};
+use crate::prelude::*;
+use crate::utils::formatter::identifier;
+use crate::utils::fs::create_file;
+use crate::utils::template;
+use crate::utils::tips;
+use crate::utils::vcs::git;
+use crate::validator::validate_semver;
+
+/// Project templates keyed by the path they are rendered to.
+const TEMPLATES: [(&str, &str); 4] = [
+    ("src/main.sh", "project/main.sh"),
+    ("src/lib/common.sh", "project/lib/common.sh"),
+    (".gitignore", "project/gitignore"),
+    ("README.md", "project/README.md"),
+];
+const LICENSE_NAME: &str = "LICENSE";
+const LICENSE_TEMPLATE: &str = "project/LICENSE-MIT";
+
+/// Create a new shinc project.
+#[derive(clap::Parser, Debug)]
+pub struct NewCmd {
+    /// Directory of the new project
+    path: PathBuf,
+    #[command(flatten)]
+    project: ProjectArgs,
+}
+
+/// Options used to fill the `[project]` section of the configuration.
+#[derive(clap::Args, Debug)]
+pub struct ProjectArgs {
+    /// Project name, defaults to the directory name
+    #[arg(long)]
+    name: Option<String>,
+    /// Project version
+    #[arg(long, default_value = "0.1.0", value_parser = validate_semver)]
+    version: String,
+    /// Project description
+    #[arg(long)]
+    description: Option<String>,
+    /// Project license
+    #[arg(long, default_value = "MIT")]
+    license: String,
+    /// Project repository URL, defaults to the URL of the `origin` remote
+    #[arg(long, value_name = "URL")]
+    repository: Option<url::Url>,
+    /// Copyright holder of the license, defaults to `git config user.name`
+    #[arg(long)]
+    author: Option<String>,
+    /// Do not initialize a git repository
+    #[arg(long)]
+    no_git: bool,
+}
+
+impl CliCommand for NewCmd {
+    fn run(&self) -> CliResult {
+        if self.path.exists() {
+            anyhow::bail!("destination '{}' already exists", self.path.display());
+        }
+        let root = std::env::current_dir()?.join(&self.path);
+        scaffold(&root, &self.project)?;
+
+        tips::h1("Next steps");
+        println!("cd {} && shinc build", self.path.display());
+        Ok(())
+    }
+}
+
+/// Lays out a working project in `root`, keeping any existing files.
+pub fn scaffold(root: &Path, args: &ProjectArgs) -> CliResult {
+    let config_path = Config::locate_config_path(root);
+    if config_path.exists() {
+        anyhow::bail!("project already initialized: '{}'", config_path.display());
+    }
+
+    let name = match &args.name {
+        Some(name) => name.clone(),
+        None => root
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow::format_err!("failed to infer project name, use --name"))?,
+    };
+    tips::title(&format!("Creating project {}", identifier(&name)));
+
+    let repo = if args.no_git {
+        None
+    } else if let Ok(repo) = git::Repo::new(root) {
+        Some(repo)
+    } else if !git::is_inside_work_tree(root) {
+        tips::h1("Initializing git repository");
+        Some(git::Repo::init(root)?)
+    } else {
+        None
+    };
+    let repository = args.repository.clone().or_else(|| {
+        let url = repo.as_ref()?.remote_url("origin").ok()??;
+        https_url(&url)
+    });
+
+    let description =
+        args.description.clone().unwrap_or_else(|| format!("The {name} command line tool"));
+    let author = args.author.clone().or_else(|| git::config_string("user.name"));
+    let mut ctx = tera::Context::new();
+    ctx.insert("name", &name);
+    ctx.insert("version", &args.version);
+    ctx.insert("description", &description);
+    ctx.insert("author", author.as_deref().unwrap_or(&name));
+    ctx.insert("year", &chrono::Local::now().year());
+    let engine = init_engine(root)?;
+
+    tips::h1("Generating");
+    let config = render_config(root, &name, args, repository.as_ref())?;
+    write_file(&config_path, &config)?;
+    for (path, name) in TEMPLATES {
+        write_file(root.join(path), &engine.render(name, &ctx)?)?;
+    }
+    if args.license == "MIT" {
+        write_file(root.join(LICENSE_NAME), &engine.render(LICENSE_TEMPLATE, &ctx)?)?;
+    } else {
+        tips::warning(&format!("No template for license '{}', skipping", args.license));
+    }
+
+    Ok(())
+}
+
+fn init_engine(root: &Path) -> anyhow::Result<tera::Tera> {
+    let mut names: Vec<_> = TEMPLATES.iter().map(|(_, name)| *name).collect();
+    names.push(LICENSE_TEMPLATE);
+    template::engine(root, &names)
+}
+
+fn render_config(
+    root: &Path,
+    name: &str,
+    args: &ProjectArgs,
+    repository: Option<&url::Url>,
+) -> anyhow::Result<String> {
+    let mut doc: toml_edit::DocumentMut = template::load(root, DEFAULT_CONFIG_NAME)?.parse()?;
+    let project = &mut doc["project"];
+    project["name"] = toml_edit::value(name);
+    project["version"] = toml_edit::value(&args.version);
+    if let Some(description) = &args.description {
+        project["description"] = toml_edit::value(description);
+    }
+    if let Some(repository) = repository {
+        project["repository"] = toml_edit::value(repository.as_str());
+    }
+    project["license"] = toml_edit::value(&args.license);
+    Ok(doc.to_string())
+}
+
+fn write_file<P: AsRef<Path>>(path: P, content: &str) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        tips::warning(&format!("'{}' already exists, skipping", path.display()));
+        return Ok(());
+    }
+    create_file(path)?.write_all(content.as_bytes())?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// Converts a git remote URL to its `https` form.
+fn https_url(remote: &str) -> Option<url::Url> {
+    let remote = remote.trim_end_matches(".git");
+    let url = match remote.split_once('@').and_then(|(_, x)| x.split_once(':')) {
+        Some((host, path)) if !remote.contains("://") => format!("https://{host}/{path}"),
+        _ => remote.to_string(),
+    };
+    url::Url::parse(&url).ok().filter(|u| u.scheme() == "https")
+}
@@ -0,0 +1,110 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use super::dist::archive_checksum;
+use crate::prelude::*;
+use crate::utils::archive::ArchiveFormat;
+use crate::utils::fs::create_file;
+use crate::utils::template;
+
+const DERIVATION_NAME: &str = "nix/default.nix";
+const FLAKE_NAME: &str = "nix/flake.nix";
+
+/// Generate a Nix derivation.
+#[derive(clap::Parser, Debug)]
+pub struct NixCmd {
+    /// Package name if not specified using inferred name
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Write the derivation file to directory instead of stdout
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+    /// Generate a `flake.nix` instead of a `default.nix`
+    #[arg(long)]
+    pub flake: bool,
+}
+
+impl NixCmd {
+    fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| CONFIG.inferred_dist_name())
+    }
+
+    fn filename(&self) -> &'static str {
+        if self.flake { "flake.nix" } else { "default.nix" }
+    }
+}
+
+/// Renders the derivation of the project at `root`, wrapped in a flake if
+/// `flake` is set.
+fn render(root: &Path, ctx: &mut tera::Context, flake: bool) -> anyhow::Result<String> {
+    let engine = template::engine(root, &[DERIVATION_NAME, FLAKE_NAME])?;
+    let derivation = engine.render(DERIVATION_NAME, ctx)?;
+    if !flake {
+        return Ok(derivation);
+    }
+    ctx.insert("derivation", &derivation);
+    Ok(engine.render(FLAKE_NAME, ctx)?)
+}
+
+impl CliCommand for NixCmd {
+    fn run(&self) -> CliResult {
+        let project = CONFIG.project();
+        let Some(url) = CONFIG.dist_archive_url(ArchiveFormat::TarGz) else {
+            anyhow::bail!("Repository URL not set in configuration");
+        };
+        let checksum = hex::decode(archive_checksum(ArchiveFormat::TarGz)?)?;
+        let mut ctx = tera::Context::new();
+        ctx.insert("name", &self.name());
+        ctx.insert("version", project.version());
+        ctx.insert("url", &url);
+        ctx.insert("hash", &format!("sha256-{}", STANDARD.encode(checksum)));
+        ctx.insert("prefix", &CONFIG.dist_prefix()?);
+        ctx.insert("project", project);
+        ctx.insert("bins", &CONFIG.bins());
+        let content = render(CONFIG.root(), &mut ctx, self.flake)?;
+        if let Some(dir) = &self.output_dir {
+            create_file(dir.join(self.filename()))?.write_all(content.as_bytes())?;
+        } else {
+            io::stdout().write_all(content.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let project = BTreeMap::from([
+            ("description", r#"Say "hi" to ${USER} \o/"#),
+            ("homepage", "https://example.com"),
+        ]);
+        let mut ctx = tera::Context::new();
+        ctx.insert("name", "hello");
+        ctx.insert("version", "1.0.0");
+        ctx.insert("url", "https://example.com/hello.tar.gz");
+        ctx.insert("hash", "sha256-abc");
+        ctx.insert("prefix", "");
+        ctx.insert("project", &project);
+        ctx.insert("bins", &[BTreeMap::from([("name", "hello")])]);
+        let root = std::env::temp_dir().join("test_render_nix");
+        let description = r#"description = "Say \"hi\" to \${USER} \\o/";"#;
+
+        let content = render(&root, &mut ctx, false).unwrap();
+        assert!(content.contains(description));
+        assert!(content.contains(r#"homepage = "https://example.com";"#));
+        assert!(content.contains(r#"mainProgram = "hello";"#));
+        assert!(!content.contains("license ="));
+
+        let content = render(&root, &mut ctx, true).unwrap();
+        assert!(content.starts_with(&format!("{{\n  {description}\n")));
+        assert!(content.contains(r#""hello" = pkgs.callPackage ("#));
+    }
+}
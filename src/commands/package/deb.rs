@@ -0,0 +1,107 @@
+use super::{collect_files, package_info, package_tools, report};
+use crate::prelude::*;
+use crate::utils::package::deb::{deb_filename, write_deb};
+use crate::utils::tips;
+
+/// The directory of zsh completions on Debian.
+const ZSH_DIR: &str = "/usr/share/zsh/vendor-completions";
+/// Tools of the essential packages, which are installed on every Debian
+/// system and are never declared as dependencies.
+const ESSENTIAL_TOOLS: &[&str] = &[
+    "awk",
+    "base64",
+    "basename",
+    "bash",
+    "cat",
+    "chmod",
+    "chown",
+    "cmp",
+    "comm",
+    "cp",
+    "cut",
+    "date",
+    "dd",
+    "df",
+    "diff",
+    "dirname",
+    "du",
+    "echo",
+    "env",
+    "expr",
+    "false",
+    "find",
+    "fold",
+    "grep",
+    "gzip",
+    "head",
+    "hostname",
+    "id",
+    "install",
+    "join",
+    "ln",
+    "ls",
+    "md5sum",
+    "mkdir",
+    "mktemp",
+    "mv",
+    "nl",
+    "nproc",
+    "od",
+    "paste",
+    "printf",
+    "pwd",
+    "readlink",
+    "realpath",
+    "rm",
+    "rmdir",
+    "sed",
+    "seq",
+    "sh",
+    "sha256sum",
+    "sleep",
+    "sort",
+    "split",
+    "stat",
+    "tac",
+    "tail",
+    "tar",
+    "tee",
+    "touch",
+    "tr",
+    "true",
+    "uname",
+    "uniq",
+    "wc",
+    "which",
+    "whoami",
+    "xargs",
+    "yes",
+    "zcat",
+];
+
+/// Build a Debian package (.deb) from the built files.
+#[derive(clap::Parser, Debug)]
+pub struct PackageDebCmd {}
+
+impl CliCommand for PackageDebCmd {
+    fn run(&self) -> CliResult {
+        tips::debug("Building Debian package");
+
+        let files = collect_files(ZSH_DIR)?;
+        let mut depends = vec!["bash".to_string()];
+        for tool in package_tools(&files, ZSH_DIR)? {
+            let package = match CONFIG.deb_package(&tool) {
+                Some(package) => package,
+                None if ESSENTIAL_TOOLS.contains(&tool.as_str()) => continue,
+                None => &tool,
+            };
+            if !package.is_empty() && !depends.iter().any(|x| x == package) {
+                depends.push(package.to_string());
+            }
+        }
+        let info = package_info(depends)?;
+        let output = CONFIG.package_dir().join(deb_filename(&info));
+        report(&output);
+        write_deb(&output, &info, &files)
+    }
+}
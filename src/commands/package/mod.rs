@@ -0,0 +1,151 @@
+mod deb;
+mod rpm;
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use super::build::cache::BuildManifest;
+use super::dist::source_date_epoch;
+use crate::prelude::*;
+use crate::utils::package::{PackageFile, PackageInfo, gzip, package_version};
+use crate::utils::tips;
+use crate::utils::vcs::git;
+
+const BASH_COMPLETION_DIR: &str = "/usr/share/bash-completion/completions";
+const FISH_COMPLETION_DIR: &str = "/usr/share/fish/vendor_completions.d";
+
+/// Build native Linux packages.
+#[derive(clap::Subcommand, shinc_derive::CliCommand, Debug)]
+pub enum PackageCmd {
+    Deb(deb::PackageDebCmd),
+    Rpm(rpm::PackageRpmCmd),
+}
+
+/// Returns the metadata of the package from `[project]` and `[package]`.
+fn package_info(depends: Vec<String>) -> anyhow::Result<PackageInfo> {
+    let project = CONFIG.project();
+    let name = CONFIG.inferred_dist_name().to_lowercase();
+    Ok(PackageInfo {
+        summary: project.description().map_or_else(|| name.clone(), str::to_string),
+        name,
+        version: package_version(project.version()),
+        release: CONFIG.package_release().to_string(),
+        license: project.license().map(str::to_string),
+        homepage: project.homepage().or(project.repository()).map(|x| x.to_string()),
+        maintainer: maintainer()?,
+        depends,
+        mtime: source_date_epoch()?,
+    })
+}
+
+/// Returns `[package].maintainer`, or the name and email of the git user.
+pub fn maintainer() -> anyhow::Result<String> {
+    if let Some(maintainer) = CONFIG.package_maintainer() {
+        return Ok(maintainer.to_string());
+    }
+    match (git::config_string("user.name"), git::config_string("user.email")) {
+        (Some(name), Some(email)) => Ok(format!("{name} <{email}>")),
+        _ => anyhow::bail!(
+            "no maintainer, set `[package].maintainer` or `git config user.name` and `user.email`"
+        ),
+    }
+}
+
+/// Returns the tools declared with `@meta require-tools` by every bin, as
+/// recorded by `shinc build`.
+fn required_tools() -> anyhow::Result<BTreeSet<String>> {
+    let manifest = BuildManifest::load(CONFIG.build_manifest_file());
+    let mut tools = BTreeSet::new();
+    for bin in CONFIG.bins() {
+        let Some(bin_tools) = manifest.tools(bin.name()) else {
+            anyhow::bail!("'{}' has not been built, run `shinc build` first", bin.name());
+        };
+        tools.extend(bin_tools.iter().cloned());
+    }
+    Ok(tools)
+}
+
+/// Returns the tools the packaged files need: those of `required_tools`, and
+/// `argc` if there are completions, as their scripts call it to list the
+/// candidates.
+fn package_tools(files: &[PackageFile], zsh_dir: &str) -> anyhow::Result<BTreeSet<String>> {
+    let mut tools = required_tools()?;
+    let completion_dirs = [BASH_COMPLETION_DIR, FISH_COMPLETION_DIR, zsh_dir];
+    if files.iter().any(|file| completion_dirs.iter().any(|dir| file.path.starts_with(dir))) {
+        tools.insert("argc".to_string());
+    }
+    Ok(tools)
+}
+
+/// Lists the built files at their standard paths: the bins in `/usr/bin`,
+/// the man pages in `/usr/share/man` and the bash, fish and zsh completions,
+/// zsh ones in `zsh_dir`, which differs between distributions.
+fn collect_files(zsh_dir: &str) -> anyhow::Result<Vec<PackageFile>> {
+    let mut files = vec![];
+    for bin in CONFIG.bins() {
+        let source = CONFIG.bin_file(bin.name());
+        files.push(PackageFile {
+            path: Path::new("/usr/bin").join(bin.name()),
+            content: read(&source)?,
+            mode: 0o755,
+        });
+    }
+
+    for source in list_files(&CONFIG.man_dir())? {
+        let filename = source.file_name().unwrap_or_default().to_string_lossy();
+        let Some(section) = source.extension().map(|x| x.to_string_lossy()) else {
+            continue;
+        };
+        files.push(PackageFile {
+            path: PathBuf::from(format!("/usr/share/man/man{section}/{filename}.gz")),
+            content: gzip(&read(&source)?)?,
+            mode: 0o644,
+        });
+    }
+
+    let comp_dir = CONFIG.comp_dir();
+    for source in list_files(&comp_dir)? {
+        let filename = source.file_name().unwrap_or_default().to_string_lossy();
+        let path = match source.strip_prefix(&comp_dir)?.iter().next().and_then(|x| x.to_str()) {
+            Some("bash") => {
+                format!("{BASH_COMPLETION_DIR}/{}", filename.trim_end_matches(".bash"))
+            }
+            Some("fish") => format!("{FISH_COMPLETION_DIR}/{filename}"),
+            Some("zsh") => format!("{zsh_dir}/{filename}"),
+            _ => continue,
+        };
+        files.push(PackageFile { path: path.into(), content: read(&source)?, mode: 0o644 });
+    }
+    Ok(files)
+}
+
+/// Returns the files under `dir`, which may not exist.
+fn list_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        log::warn!("{} not found, skipping.", dir.display());
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+    fs::read(path).map_err(|e| {
+        anyhow::format_err!("failed to read '{}', run `shinc build` first: {e}", path.display())
+    })
+}
+
+/// Prints the output path of a package.
+fn report(output: &Path) {
+    tips::h1("Packaging files");
+    println!("{}", output.display());
+}
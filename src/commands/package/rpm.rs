@@ -0,0 +1,28 @@
+use super::{collect_files, package_info, package_tools, report};
+use crate::prelude::*;
+use crate::utils::package::rpm::{rpm_filename, write_rpm};
+use crate::utils::tips;
+
+/// The directory of zsh completions on Fedora and openSUSE.
+const ZSH_DIR: &str = "/usr/share/zsh/site-functions";
+
+/// Build an RPM package (.rpm) from the built files.
+#[derive(clap::Parser, Debug)]
+pub struct PackageRpmCmd {}
+
+impl CliCommand for PackageRpmCmd {
+    fn run(&self) -> CliResult {
+        tips::debug("Building RPM package");
+
+        // Tools are required by path, so dnf finds whichever package provides
+        // them.
+        let files = collect_files(ZSH_DIR)?;
+        let mut depends = vec!["bash".to_string()];
+        depends
+            .extend(package_tools(&files, ZSH_DIR)?.iter().map(|tool| format!("/usr/bin/{tool}")));
+        let info = package_info(depends)?;
+        let output = CONFIG.package_dir().join(rpm_filename(&info));
+        report(&output);
+        write_rpm(&output, &info, &files)
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::dist::archive_checksum;
+use super::package::maintainer;
+use crate::prelude::*;
+use crate::utils::archive::ArchiveFormat;
+use crate::utils::fs::create_file;
+use crate::utils::template;
+
+const PKGBUILD_NAME: &str = "arch/PKGBUILD";
+
+/// Generate an Arch Linux PKGBUILD.
+#[derive(clap::Parser, Debug)]
+pub struct PkgbuildCmd {
+    /// Package name if not specified using inferred name
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Write PKGBUILD file to directory instead of stdout
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+}
+
+impl PkgbuildCmd {
+    fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| CONFIG.inferred_dist_name())
+    }
+}
+
+/// Renders the PKGBUILD template of the project at `root`.
+fn render(root: &Path, ctx: &tera::Context) -> anyhow::Result<String> {
+    let engine = template::engine(root, &[PKGBUILD_NAME])?;
+    Ok(engine.render(PKGBUILD_NAME, ctx)?)
+}
+
+impl CliCommand for PkgbuildCmd {
+    fn run(&self) -> CliResult {
+        let project = CONFIG.project();
+        let Some(url) = CONFIG.dist_archive_url(ArchiveFormat::TarGz) else {
+            anyhow::bail!("Repository URL not set in configuration");
+        };
+        let mut ctx = tera::Context::new();
+        ctx.insert("name", &self.name().to_lowercase());
+        // pacman sorts `1.0.0rc.1` before `1.0.0`, and forbids `-`.
+        ctx.insert("version", &project.version().replace('-', ""));
+        ctx.insert("url", &url);
+        ctx.insert("checksum", &archive_checksum(ArchiveFormat::TarGz)?);
+        ctx.insert("prefix", &CONFIG.dist_prefix()?);
+        ctx.insert("maintainer", &maintainer().unwrap_or_default());
+        ctx.insert("project", project);
+        let content = render(CONFIG.root(), &ctx)?;
+        if let Some(dir) = &self.output_dir {
+            create_file(dir.join("PKGBUILD"))?.write_all(content.as_bytes())?;
+        } else {
+            io::stdout().write_all(content.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut project = BTreeMap::from([("description", r#"Say "hi" for $5 `now`"#)]);
+        let mut ctx = tera::Context::new();
+        ctx.insert("name", "hello");
+        ctx.insert("version", "1.0.0rc.1");
+        ctx.insert("url", "https://example.com/hello.tar.gz");
+        ctx.insert("checksum", "abc");
+        ctx.insert("prefix", "");
+        ctx.insert("maintainer", "");
+        ctx.insert("project", &project);
+        let root = std::env::temp_dir().join("test_render_pkgbuild");
+        let content = render(&root, &ctx).unwrap();
+        assert!(content.contains(r#"pkgdesc="Say \"hi\" for \$5 \`now\`""#));
+        assert!(content.contains("\nurl=\"\"\n"));
+        assert!(!content.contains("license="));
+        assert!(!content.contains("# Maintainer"));
+
+        project.insert("license", "MIT");
+        ctx.insert("project", &project);
+        ctx.insert("prefix", "hello-v1.0.0");
+        let content = render(&root, &ctx).unwrap();
+        assert!(content.contains("\nlicense=(\"MIT\")\n"));
+        assert!(content.contains(r#"cd "${srcdir}/hello-v1.0.0""#));
+    }
+}
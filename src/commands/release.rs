@@ -1,24 +1,32 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::str::FromStr;
 
+use anyhow::Context;
 use clap::{Parser, ValueHint};
+use globset::Glob;
+use semver::Version;
 
 use crate::prelude::*;
+use crate::utils::diff::unified_diff;
 use crate::utils::formatter::identifier;
 use crate::utils::path::add_extension;
-use crate::utils::process::quit_if;
-use crate::utils::terminal::confirm_or_exit;
+use crate::utils::terminal::confirm;
 use crate::utils::tips;
 use crate::utils::vcs::git;
-use crate::validator::validate_semver;
+use crate::utils::version::{self, Bump, VersionSpec};
 
 /// Create a new release
 #[derive(clap::Parser, Debug)]
 pub struct ReleaseCmd {
-    /// Semantic version number
-    #[arg(value_hint = ValueHint::Other, value_parser = validate_semver)]
-    version: String,
+    /// Semantic version number, or `auto`, `major`, `minor`, `patch` or `pre`
+    /// to bump the version of the last `v*` tag
+    #[arg(value_hint = ValueHint::Other, value_parser = VersionSpec::from_str)]
+    version: VersionSpec,
+    /// Pre-release identifier, e.g. `rc`, only valid with `pre`
+    #[arg(value_name = "ID", required_if_eq("version", "pre"))]
+    pre_id: Option<String>,
     /// Skip release confirmation
     #[arg(long)]
     no_confirm: bool,
@@ -34,6 +42,208 @@ pub struct ReleaseCmd {
     /// Git remote to push
     #[arg(long, value_name = "NAME", default_value = "origin")]
     remote_name: String,
+    /// Print the release plan without changing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// What a release does, as printed by `--dry-run`.
+struct Plan<'a> {
+    version: &'a str,
+    tag: &'a str,
+    prev_tag: Option<&'a str>,
+    branch: &'a str,
+    message: String,
+    /// The changelog and the config file, relative to the repository.
+    files: [PathBuf; 2],
+    problems: Vec<String>,
+}
+
+impl Plan<'_> {
+    /// Returns the environment variables of the hooks.
+    fn env(&self) -> [(&str, &str); 3] {
+        [
+            ("SHINC_VERSION", self.version),
+            ("SHINC_TAG", self.tag),
+            ("SHINC_PREV_TAG", self.prev_tag.unwrap_or_default()),
+        ]
+    }
+}
+
+impl ReleaseCmd {
+    /// Returns the last `v*` tag and the version to release, computed from
+    /// the version of that tag, or `[project].version` if there is none.
+    fn next_version(&self, repo: &git::Repo) -> anyhow::Result<(Option<String>, Version)> {
+        if self.pre_id.is_some() && self.version != VersionSpec::Pre {
+            anyhow::bail!("The ID argument is only valid with `pre`, e.g. `shinc release pre rc`");
+        }
+        let (prev_tag, base) = match repo.latest_version_tag()? {
+            Some((tag, version)) => (Some(tag), version),
+            None => (None, Version::parse(CONFIG.project().version())?),
+        };
+        let auto = || {
+            cliff_bump(&base)?.ok_or_else(|| match &prev_tag {
+                Some(tag) => anyhow::format_err!("No commits since '{tag}'"),
+                None => anyhow::format_err!("No commits to release"),
+            })
+        };
+        let next = match &self.version {
+            VersionSpec::Exact(version) => version.clone(),
+            VersionSpec::Auto => auto()?.apply(&base),
+            VersionSpec::Bump(bump) => bump.apply(&base),
+            VersionSpec::Pre => {
+                version::pre_release(&base, self.pre_id.as_deref().unwrap_or_default(), auto)?
+            }
+        };
+        Ok((prev_tag, next))
+    }
+
+    /// Returns the problems found by the checks enabled in `[release]`: the
+    /// tracked files other than the `edited` ones must be committed, `branch`
+    /// must be allowed and must not be behind its upstream.
+    fn check(
+        &self,
+        repo: &git::Repo,
+        branch: &str,
+        edited: &[PathBuf],
+    ) -> anyhow::Result<Vec<String>> {
+        let mut problems = vec![];
+        if CONFIG.release_check_clean() {
+            let changed: Vec<_> = repo
+                .changed_files()?
+                .into_iter()
+                .filter(|file| !edited.iter().any(|x| x == Path::new(file)))
+                .collect();
+            if !changed.is_empty() {
+                problems.push(format!(
+                    "Uncommitted changes in {}, commit or stash them \
+                     (or set `[release].check_clean = false`)",
+                    changed.join(", ")
+                ));
+            }
+        }
+        let branches = CONFIG.release_branches();
+        if !is_branch_allowed(branch, branches)? {
+            problems.push(format!(
+                "Branch '{branch}' is not one of `[release].branches`: {}, switch branches \
+                 (or set `[release].branches = []`)",
+                branches.join(", ")
+            ));
+        }
+        if CONFIG.release_check_upstream() {
+            match repo.behind_upstream(branch) {
+                Ok(Some((upstream, behind))) if behind > 0 => problems.push(format!(
+                    "Branch '{branch}' is {behind} commit(s) behind '{upstream}', pull first \
+                     (or set `[release].check_upstream = false`)"
+                )),
+                Ok(Some(_)) => {}
+                Ok(None) => log::debug!("Branch '{branch}' has no upstream"),
+                Err(e) => problems.push(format!(
+                    "Failed to compare branch '{branch}' with its upstream: {e} \
+                     (or set `[release].check_upstream = false`)"
+                )),
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Prints `plan`, the edit of `[project].version` in `config_path`, and
+    /// the changelog section of the release.
+    fn print_plan(&self, plan: &Plan, config_path: PathBuf) -> CliResult {
+        let skipped = |skip: bool, step: &str| if skip { "skipped" } else { step }.to_string();
+        let commit = skipped(self.no_commit, &plan.message);
+        let tag = skipped(self.no_commit || self.no_tag, plan.tag);
+        let push = skipped(
+            self.no_commit || self.no_tag || self.no_push,
+            &format!("{} {} {}", self.remote_name, plan.branch, plan.tag),
+        );
+        tips::h1("Release plan");
+        println!("Version: {}", plan.version);
+        println!("Previous tag: {}", plan.prev_tag.unwrap_or("none"));
+        println!("Branch: {}", plan.branch);
+        println!("Commit: {commit}\nTag: {tag}\nPush: {push}");
+
+        let hooks = CONFIG.release_hooks();
+        let hooks = [
+            ("pre_bump", hooks.pre_bump()),
+            ("post_bump", hooks.post_bump()),
+            ("pre_commit", hooks.pre_commit()),
+            ("post_tag", hooks.post_tag()),
+            ("post_push", hooks.post_push()),
+        ];
+        if hooks.iter().any(|(_, commands)| !commands.is_empty()) {
+            tips::h1("Hooks");
+            for (name, commands) in hooks {
+                for command in commands {
+                    println!("{name}: {command}");
+                }
+            }
+        }
+
+        tips::h1("Checks");
+        if plan.problems.is_empty() {
+            println!("All passed");
+        }
+        for problem in &plan.problems {
+            tips::warning(problem);
+        }
+
+        tips::h1("Version diff");
+        let content = fs::read_to_string(&config_path)?;
+        let updated = set_project_version(&content, plan.version)?;
+        let (a, b) =
+            (format!("a/{}", config_path.display()), format!("b/{}", config_path.display()));
+        match unified_diff(&content, &updated, (&a, &b)) {
+            Some(diff) => print!("{diff}"),
+            None => println!("No change"),
+        }
+
+        tips::h1("Changelog");
+        let section = generate_changelog(&["--unreleased", "--tag", plan.tag, "--strip", "all"])?;
+        println!("{}", section.trim());
+        Ok(())
+    }
+
+    /// Runs the steps of the release up to the push, recording in `tx` what
+    /// to undo if one fails. Returns whether the release was pushed.
+    fn execute(&self, plan: &Plan, tx: &mut Transaction, workdir: &Path) -> anyhow::Result<bool> {
+        let hooks = CONFIG.release_hooks();
+        let env = plan.env();
+        let run_hooks = |name, commands| step(name, run_hooks(workdir, name, commands, &env));
+        run_hooks("pre_bump", hooks.pre_bump())?;
+
+        tips::h1("Updating version");
+        step("bump version", update_project_version(CONFIG.path(), plan.version))?;
+
+        tips::h1("Updating changelog");
+        step("update changelog", update_changelog(CONFIG.changelog(), plan.tag))?;
+        run_hooks("post_bump", hooks.post_bump())?;
+        if self.no_commit {
+            return Ok(false);
+        }
+
+        run_hooks("pre_commit", hooks.pre_commit())?;
+        tips::h1("Committing changes");
+        tx.head = Some(tx.repo.head_id()?);
+        step("commit", tx.repo.commit(&plan.files, &plan.message))?;
+        if self.no_tag {
+            return Ok(false);
+        }
+
+        tips::h1("Creating tag");
+        step("tag", tx.repo.create_tag(plan.tag, &plan.message))?;
+        tx.tag = Some(plan.tag.to_string());
+        run_hooks("post_tag", hooks.post_tag())?;
+        if self.no_push {
+            return Ok(false);
+        }
+
+        tips::h1("Pushing commits and tag");
+        let output =
+            step("push", push_branch_and_tag(workdir, &self.remote_name, plan.branch, plan.tag))?;
+        println!("{output}");
+        Ok(true)
+    }
 }
 
 impl CliCommand for ReleaseCmd {
@@ -44,53 +254,190 @@ impl CliCommand for ReleaseCmd {
 
         let current_dir = std::env::current_dir()?;
         let repo = git::Repo::new(&current_dir)?;
-        let tag = format!("v{}", self.version);
+        let (prev_tag, version) = self.next_version(&repo)?;
+        let version = version.to_string();
+        let tag = format!("v{version}");
         if repo.tag_exists(&tag)? {
-            tips::die(&format!("The tag '{tag}' already exists"));
+            anyhow::bail!("The tag '{tag}' already exists");
         }
 
         let name = CONFIG.bins().iter().map(|b| b.name()).collect::<Vec<_>>().join(" ");
         let branch = repo.current_branch()?;
+        let config_file = relative_path(&current_dir, CONFIG.path())?;
+        let files = [PathBuf::from(CONFIG.changelog()), config_file.clone()];
+        let plan = Plan {
+            version: &version,
+            tag: &tag,
+            prev_tag: prev_tag.as_deref(),
+            branch: &branch,
+            message: format!("chore: Release {name} {version}"),
+            problems: self.check(&repo, &branch, &files)?,
+            files,
+        };
+        if self.dry_run {
+            return self.print_plan(&plan, config_file);
+        }
+        if !plan.problems.is_empty() {
+            anyhow::bail!("Refusing to release:\n- {}", plan.problems.join("\n- "));
+        }
+
         if !self.no_confirm {
-            confirm_or_exit(&format!("Release {} {}", identifier(&name), self.version));
-            confirm_or_exit(&format!("Branch: {}", identifier(&branch)));
+            let change = match &prev_tag {
+                Some(prev_tag) => format!("({prev_tag} -> {tag})"),
+                None => format!("({tag})"),
+            };
+            if !confirm(&format!("Release {} {version} {change}", identifier(&name)))?
+                || !confirm(&format!("Branch: {}", identifier(&branch)))?
+            {
+                return Ok(());
+            }
         }
 
         tips::h1("Git info");
-        println!("Version: {}\nTag: {}\nBranch: {}", self.version, tag, branch);
-        tips::h1("Updating version");
-        let config_path = CONFIG.path();
-        update_project_version(&config_path, &self.version)?;
+        println!("Version: {version}\nTag: {tag}\nBranch: {branch}");
+        let mut tx =
+            Transaction { repo: &repo, backup: Backup::new(&plan.files)?, head: None, tag: None };
+        match self.execute(&plan, &mut tx, &current_dir) {
+            // The release is public once pushed, so it is not undone anymore.
+            Ok(true) => step(
+                "post_push",
+                run_hooks(
+                    &current_dir,
+                    "post_push",
+                    CONFIG.release_hooks().post_push(),
+                    &plan.env(),
+                ),
+            ),
+            Ok(false) => Ok(()),
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
 
-        tips::h1("Updating changelog");
-        let changelog = CONFIG.changelog();
-        update_changelog(changelog, &tag)?;
+/// Names the release step `name` in the error of `result`.
+fn step<T>(name: &str, result: anyhow::Result<T>) -> anyhow::Result<T> {
+    result.with_context(|| format!("Release failed at step '{name}'"))
+}
 
-        quit_if(self.no_commit);
-        tips::h1("Committing changes");
-        let files = [PathBuf::from(changelog), relative_path(&current_dir, config_path)?];
-        let message = format!("chore: Release {} {}", name, self.version);
-        repo.commit(&files, &message)?;
+/// The changes made by a release so far, to undo them when a step fails.
+struct Transaction<'a> {
+    repo: &'a git::Repo,
+    backup: Backup,
+    /// `HEAD` before the release commit, once it is attempted.
+    head: Option<git2::Oid>,
+    /// The release tag, once it is created.
+    tag: Option<String>,
+}
 
-        quit_if(self.no_tag);
-        tips::h1("Creating tag");
-        repo.create_tag(&tag, &message)?;
+impl Transaction<'_> {
+    /// Deletes the tag, resets the commit and restores the edited files,
+    /// going on when one of them fails.
+    fn rollback(&self) {
+        tips::h1("Rolling back");
+        let report = |done: &str, result: anyhow::Result<()>| match result {
+            Ok(()) => println!("{done}"),
+            Err(e) => tips::error(&format!("{done} failed: {e}")),
+        };
+        if let Some(tag) = &self.tag {
+            report(&format!("Deleted tag '{tag}'"), self.repo.delete_tag(tag));
+        }
+        if let Some(head) = self.head {
+            report("Reset the release commit", self.repo.reset_mixed(head));
+        }
+        report("Restored the version and changelog", self.backup.restore());
+    }
+}
 
-        quit_if(self.no_push);
-        tips::h1("Pushing commits and tag");
-        let output = push_branch_and_tag(current_dir, &self.remote_name, &branch, &tag)?;
-        println!("{output}");
+/// The contents of files before a release edits them.
+struct Backup(Vec<(PathBuf, Option<String>)>);
+
+impl Backup {
+    fn new(paths: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut files = vec![];
+        for path in paths {
+            let content = if path.exists() { Some(fs::read_to_string(path)?) } else { None };
+            files.push((path.clone(), content));
+        }
+        Ok(Self(files))
+    }
 
+    /// Writes back the saved contents, removing the files that did not
+    /// exist.
+    fn restore(&self) -> anyhow::Result<()> {
+        for (path, content) in &self.0 {
+            match content {
+                Some(content) => atomic_write(path, content)?,
+                None if path.exists() => fs::remove_file(path)?,
+                None => {}
+            }
+        }
         Ok(())
     }
 }
 
+/// Runs the `name` hook `commands` with `sh -c` in `workdir`, stopping at the
+/// first failure.
+fn run_hooks(
+    workdir: &Path,
+    name: &str,
+    commands: &[String],
+    env: &[(&str, &str)],
+) -> anyhow::Result<()> {
+    for command in commands {
+        tips::h1(&format!("Running {name} hook"));
+        println!("$ {command}");
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(workdir)
+            .envs(env.iter().copied())
+            .status()
+            .with_context(|| format!("failed to run {name} hook `{command}`"))?;
+        if !status.success() {
+            anyhow::bail!("The {name} hook `{command}` failed with {status}");
+        }
+    }
+    Ok(())
+}
+
 fn update_changelog(output: &str, tag_name: &str) -> anyhow::Result<()> {
-    let args = ["git-cliff", "--tag", tag_name, "--output", output];
-    let opts = git_cliff::args::Opt::try_parse_from(args)?;
+    let content = generate_changelog(&["--tag", tag_name])?;
+    atomic_write(output, &content)
+}
+
+/// Returns the bump git-cliff infers from the unreleased commits of `base`,
+/// as `git cliff --bump` does, so it follows the commit parsers and `[bump]`
+/// of the git-cliff configuration. `None` if there is nothing to release.
+fn cliff_bump(base: &Version) -> anyhow::Result<Option<Bump>> {
+    let opts = git_cliff::args::Opt::try_parse_from(["git-cliff", "--unreleased"])?;
+    let mut changelog = git_cliff::run(opts)?;
+    let Some(release) = changelog.releases.first_mut() else {
+        return Ok(None);
+    };
+    if release.commits.is_empty() {
+        return Ok(None);
+    }
+    // Bump from `base`, even without a previous tag, and from its release
+    // version, as a pre-release is handled by `Bump::apply`.
+    let base = Version::new(base.major, base.minor, base.patch);
+    release.previous.get_or_insert_default().version = Some(base.to_string());
+    let Some(next) = changelog.bump_version()? else {
+        return Ok(None);
+    };
+    Ok(Bump::between(&base, &Version::parse(next.trim_start_matches('v'))?))
+}
+
+/// Returns the changelog generated by git-cliff with the command line `args`.
+fn generate_changelog(args: &[&str]) -> anyhow::Result<String> {
+    let opts = git_cliff::args::Opt::try_parse_from(["git-cliff"].iter().chain(args))?;
     log::debug!("git-cliff opts: {opts:#?}");
-    git_cliff::run(opts)?;
-    Ok(())
+    let changelog = git_cliff::run(opts.clone())?;
+    let mut out = vec![];
+    git_cliff::write_changelog(&opts, changelog, &mut out)?;
+    Ok(String::from_utf8(out)?)
 }
 
 pub fn update_project_version<P: AsRef<Path>>(
@@ -98,11 +445,17 @@ pub fn update_project_version<P: AsRef<Path>>(
     new_version: &str,
 ) -> anyhow::Result<()> {
     let content = fs::read_to_string(&config_path)?;
+    let updated = set_project_version(&content, new_version)?;
+    atomic_write(config_path, &updated)?;
+    Ok(())
+}
+
+/// Returns the configuration `content` with `[project].version` set to
+/// `new_version`, keeping its formatting.
+fn set_project_version(content: &str, new_version: &str) -> anyhow::Result<String> {
     let mut doc: toml_edit::DocumentMut = content.parse()?;
     doc["project"]["version"] = toml_edit::value(new_version);
-    let parsed = doc.to_string();
-    atomic_write(config_path, &parsed)?;
-    Ok(())
+    Ok(doc.to_string())
 }
 
 fn atomic_write<P: AsRef<Path>>(p: P, data: &str) -> anyhow::Result<()> {
@@ -120,7 +473,7 @@ fn push_branch_and_tag<P: AsRef<Path>>(
     tag: &str,
 ) -> anyhow::Result<String> {
     let path = workdir.as_ref().to_str().unwrap_or_default();
-    let args = vec!["-C", path, "push", remote_name, branch, tag];
+    let args = vec!["-C", path, "push", "--atomic", remote_name, branch, tag];
     let output = Command::new("git").args(args).stderr(Stdio::piped()).output()?;
     if output.status.success() {
         Ok(String::from_utf8_lossy(&output.stderr).trim().to_string())
@@ -132,6 +485,20 @@ fn push_branch_and_tag<P: AsRef<Path>>(
     }
 }
 
+/// Returns `true` if `branch` matches one of the names or globs `patterns`,
+/// or if there are none.
+fn is_branch_allowed(branch: &str, patterns: &[String]) -> anyhow::Result<bool> {
+    if patterns.is_empty() {
+        return Ok(true);
+    }
+    for pattern in patterns {
+        if Glob::new(pattern)?.compile_matcher().is_match(branch) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 fn relative_path<W, P>(workdir: W, path: P) -> anyhow::Result<PathBuf>
 where
     W: AsRef<Path>,
@@ -140,3 +507,28 @@ where
     let relative_path = path.as_ref().strip_prefix(workdir)?;
     Ok(relative_path.to_path_buf())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_branch_allowed() {
+        let patterns = ["main".to_string(), "release/*".to_string()];
+        assert!(is_branch_allowed("main", &[]).unwrap());
+        assert!(is_branch_allowed("main", &patterns).unwrap());
+        assert!(is_branch_allowed("release/1.x", &patterns).unwrap());
+        assert!(!is_branch_allowed("feature/main", &patterns).unwrap());
+        assert!(is_branch_allowed("main", &["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_set_project_version() {
+        let content = "# The project\n[project]\nname = \"hello\" # name\nversion = \"0.1.0\"\n";
+        assert_eq!(
+            set_project_version(content, "1.0.0").unwrap(),
+            "# The project\n[project]\nname = \"hello\" # name\nversion = \"1.0.0\"\n"
+        );
+        assert!(set_project_version("[project", "1.0.0").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Context;
+use argc::{CommandValue, NativeRuntime};
+use colored::Colorize;
+use walkdir::WalkDir;
+
+use crate::config::Bin;
+use crate::prelude::*;
+use crate::utils::diff::unified_diff;
+use crate::utils::formatter::identifier;
+use crate::utils::fs::create_file;
+use crate::utils::tips;
+
+/// Record `--help` output and completions of the built scripts as snapshots.
+///
+/// New snapshots are written and changed ones are reported as failures.
+#[derive(clap::Parser, Debug)]
+pub struct SnapshotCmd {
+    /// Fail on new, changed or obsolete snapshots without writing anything
+    #[arg(long, conflicts_with = "update")]
+    check: bool,
+    /// Accept the current output, overwriting changed and removing obsolete
+    /// snapshots
+    #[arg(long)]
+    update: bool,
+}
+
+/// Snapshot contents keyed by their path relative to the snapshots directory.
+type Snapshots = BTreeMap<PathBuf, String>;
+
+impl CliCommand for SnapshotCmd {
+    fn run(&self) -> CliResult {
+        tips::debug("Snapshotting help and completions");
+
+        let mut snapshots = Snapshots::new();
+        for bin in &CONFIG.bins() {
+            tips::title(&format!("Snapshotting {}", identifier(bin.name())));
+            let bin_file = CONFIG.bin_file(bin.name());
+            if !bin_file.is_file() {
+                anyhow::bail!(
+                    "bin file not found: {}, run `shinc build` first",
+                    bin_file.display()
+                );
+            }
+            let source = fs::read_to_string(&bin_file)?;
+            let paths = command_paths(&argc::export(&source, bin.name())?);
+            collect_help(bin, &paths, &mut snapshots)?;
+            collect_completions(bin, &source, &paths, &mut snapshots)?;
+        }
+
+        let snapshots_dir = CONFIG.snapshots_dir();
+        let mut failures = 0;
+        for (path, content) in &snapshots {
+            let file = snapshots_dir.join(path);
+            if !file.is_file() {
+                if self.check {
+                    tips::error(&format!("Missing snapshot {}", path.display()));
+                    failures += 1;
+                } else {
+                    write_snapshot(&file, content)?;
+                    println!("{} {}", "new".green(), path.display());
+                }
+                continue;
+            }
+            let expected = fs::read_to_string(&file)?;
+            let header = (format!("a/{}", path.display()), format!("b/{}", path.display()));
+            if let Some(diff) = unified_diff(&expected, content, (&header.0, &header.1)) {
+                if self.update {
+                    write_snapshot(&file, content)?;
+                    println!("{} {}", "updated".yellow(), path.display());
+                } else {
+                    tips::error(&format!("Snapshot changed {}", path.display()));
+                    println!("{}", diff.trim_end());
+                    failures += 1;
+                }
+            }
+        }
+        for path in obsolete_snapshots(&snapshots_dir, &snapshots)? {
+            if self.update {
+                fs::remove_file(snapshots_dir.join(&path))?;
+                println!("{} {}", "removed".red(), path.display());
+            } else if self.check {
+                tips::error(&format!("Obsolete snapshot {}", path.display()));
+                failures += 1;
+            } else {
+                tips::warning(&format!("Obsolete snapshot {}", path.display()));
+            }
+        }
+
+        if failures > 0 {
+            anyhow::bail!("{failures} snapshot(s) failed, run `shinc snapshot --update` to accept");
+        }
+        Ok(())
+    }
+}
+
+/// Returns the paths of the bin and all of its subcommands, e.g. `[]` and
+/// `["sub"]`.
+fn command_paths(root: &CommandValue) -> Vec<Vec<String>> {
+    let mut paths = vec![];
+    let mut queue = vec![(vec![], root)];
+    while let Some((path, cmd)) = queue.pop() {
+        for sub in &cmd.subcommands {
+            let mut sub_path = path.clone();
+            sub_path.push(sub.name.clone());
+            queue.push((sub_path, sub));
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+/// Returns the snapshot name of the command at `path`, e.g. `bin-sub`.
+fn command_name(bin_name: &str, path: &[String]) -> String {
+    [bin_name].into_iter().chain(path.iter().map(String::as_str)).collect::<Vec<_>>().join("-")
+}
+
+/// Records the `--help` output of the commands at `paths`.
+fn collect_help(bin: &Bin, paths: &[Vec<String>], snapshots: &mut Snapshots) -> anyhow::Result<()> {
+    let bin_name = bin.name();
+    let bin_file = CONFIG.bin_file(bin_name);
+    for path in paths {
+        let output = Command::new(&bin_file)
+            .args(path)
+            .arg("--help")
+            .env("NO_COLOR", "1")
+            .output()
+            .with_context(|| format!("failed to execute '{}'", bin_file.display()))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "'{} {} --help' failed:\n{}",
+                bin_name,
+                path.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let file =
+            Path::new(bin_name).join("help").join(format!("{}.txt", command_name(bin_name, path)));
+        snapshots.insert(file, String::from_utf8_lossy(&output.stdout).to_string());
+    }
+    Ok(())
+}
+
+/// Records the completion candidates of the commands at `paths`, for their
+/// arguments and for their options. The completion scripts only forward to
+/// argc, so these candidates are what reflects the command spec.
+fn collect_completions(
+    bin: &Bin,
+    source: &str,
+    paths: &[Vec<String>],
+    snapshots: &mut Snapshots,
+) -> anyhow::Result<()> {
+    let bin_name = bin.name();
+    let bin_file = CONFIG.bin_file(bin_name).to_string_lossy().to_string();
+    for path in paths {
+        let mut content = String::new();
+        for word in ["", "-"] {
+            let mut args = vec![bin_name.to_string()];
+            args.extend(path.iter().cloned());
+            args.push(word.to_string());
+            let candidates =
+                argc::compgen(NativeRuntime, argc::Shell::Generic, &bin_file, source, &args, true)?;
+            content.push_str(&format!("$ {}<TAB>\n", args.join(" ")));
+            for line in candidates.lines() {
+                // Drop the display hints of the generic format, e.g. `/color:cyan`.
+                let fields: Vec<_> =
+                    line.split('\t').filter(|x| !x.starts_with("/color:")).collect();
+                content.push_str(&fields.join("\t"));
+                content.push('\n');
+            }
+        }
+        let file = Path::new(bin_name)
+            .join("completions")
+            .join(format!("{}.txt", command_name(bin_name, path)));
+        snapshots.insert(file, content);
+    }
+    Ok(())
+}
+
+/// Returns the snapshot files of the current bins that are no longer produced.
+fn obsolete_snapshots(dir: &Path, snapshots: &Snapshots) -> anyhow::Result<Vec<PathBuf>> {
+    let mut obsolete = vec![];
+    for bin in &CONFIG.bins() {
+        let bin_dir = dir.join(bin.name());
+        if !bin_dir.is_dir() {
+            continue;
+        }
+        for entry in WalkDir::new(&bin_dir).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path().strip_prefix(dir)?.to_path_buf();
+            if !snapshots.contains_key(&path) {
+                obsolete.push(path);
+            }
+        }
+    }
+    Ok(obsolete)
+}
+
+fn write_snapshot(file: &Path, content: &str) -> anyhow::Result<()> {
+    create_file(file)?
+        .write_all(content.as_bytes())
+        .with_context(|| format!("failed to write snapshot to '{}'", file.display()))
+}
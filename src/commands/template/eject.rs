@@ -0,0 +1,39 @@
+use std::io::Write;
+
+use clap::builder::PossibleValuesParser;
+
+use crate::prelude::*;
+use crate::utils::fs::create_file;
+use crate::utils::template::{EMBEDDED_TEMPLATES, embedded, override_dirs};
+use crate::utils::tips;
+
+/// Copy an embedded template out for editing.
+#[derive(clap::Parser, Debug)]
+pub struct TemplateEjectCmd {
+    /// Name of the template
+    #[arg(value_parser = PossibleValuesParser::new(EMBEDDED_TEMPLATES.map(|(name, _)| name)))]
+    name: String,
+    /// Copy to the user templates directory instead of the project one
+    #[arg(long)]
+    user: bool,
+    /// Overwrite an existing template
+    #[arg(long)]
+    force: bool,
+}
+
+impl CliCommand for TemplateEjectCmd {
+    fn run(&self) -> CliResult {
+        let Some(content) = embedded(&self.name) else {
+            anyhow::bail!("unknown template '{}'", self.name);
+        };
+        let [project_dir, user_dir] = override_dirs(std::env::current_dir()?);
+        let path = if self.user { user_dir } else { project_dir }.join(&self.name);
+        if path.exists() && !self.force {
+            anyhow::bail!("'{}' already exists, use --force to overwrite", path.display());
+        }
+        create_file(&path)?.write_all(content.as_bytes())?;
+        tips::h1("Ejecting");
+        println!("{}", path.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+use crate::prelude::*;
+use crate::utils::template::{EMBEDDED_TEMPLATES, find_override, override_dirs};
+
+/// List the templates and their overrides.
+#[derive(clap::Parser, Debug)]
+pub struct TemplateListCmd {}
+
+impl CliCommand for TemplateListCmd {
+    fn run(&self) -> CliResult {
+        let dirs = override_dirs(std::env::current_dir()?);
+        for (name, _) in EMBEDDED_TEMPLATES {
+            match find_override(&dirs, name) {
+                Some(path) => println!("{name} -> {}", path.display()),
+                None => println!("{name}"),
+            }
+        }
+        Ok(())
+    }
+}
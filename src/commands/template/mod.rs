@@ -0,0 +1,11 @@
+mod eject;
+mod list;
+
+use crate::prelude::*;
+
+/// Manage the templates of generated files.
+#[derive(clap::Subcommand, shinc_derive::CliCommand, Debug)]
+pub enum TemplateCmd {
+    Eject(eject::TemplateEjectCmd),
+    List(list::TemplateListCmd),
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::Context;
+use colored::Colorize;
+use threadpool::ThreadPool;
+use walkdir::WalkDir;
+
+use crate::prelude::*;
+use crate::utils::diff::unified_diff;
+use crate::utils::tips;
+
+/// Run test cases against the built scripts.
+#[derive(clap::Parser, Debug)]
+pub struct TestCmd {
+    /// Only run test cases whose name contains this string
+    filter: Option<String>,
+    /// Number of test cases to run in parallel
+    #[arg(short, long, value_name = "N", default_value_t = num_cpus::get())]
+    jobs: usize,
+}
+
+/// A test case loaded from a TOML or YAML file under `tests/`.
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TestCase {
+    /// Bin to run, may be omitted when the project has a single bin.
+    bin: Option<String>,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    stdin: Option<String>,
+    stdout: Option<String>,
+    stderr: Option<String>,
+    status: i32,
+}
+
+struct TestResult {
+    name: String,
+    failures: Vec<String>,
+}
+
+impl TestCase {
+    fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let case = match path.extension().and_then(|x| x.to_str()) {
+            Some("toml") => toml_edit::de::from_str(&content)?,
+            _ => serde_yaml::from_str(&content)?,
+        };
+        Ok(case)
+    }
+
+    fn bin_file(&self) -> anyhow::Result<PathBuf> {
+        let name = match &self.bin {
+            Some(name) => name.clone(),
+            None => match CONFIG.bins().as_slice() {
+                [bin] => bin.name().to_string(),
+                _ => anyhow::bail!("`bin` is required when the project has several bins"),
+            },
+        };
+        let bin_file = CONFIG.bin_file(&name);
+        if !bin_file.is_file() {
+            anyhow::bail!("bin file not found: {}", bin_file.display());
+        }
+        Ok(bin_file)
+    }
+
+    /// Runs the case and returns the list of failed expectations.
+    fn run(&self, bin_file: &Path) -> anyhow::Result<Vec<String>> {
+        let mut child = Command::new(bin_file)
+            .args(&self.args)
+            .envs(&self.env)
+            .current_dir(CONFIG.root())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to execute '{}'", bin_file.display()))?;
+        // Feed stdin from another thread so a chatty script cannot deadlock on
+        // a full stdout pipe; write errors mean the script stopped reading.
+        let stdin = child.stdin.take();
+        let input = self.stdin.clone().unwrap_or_default();
+        let writer = thread::spawn(move || stdin.map(|mut x| x.write_all(input.as_bytes())));
+        let output = child.wait_with_output()?;
+        let _ = writer.join();
+
+        let mut failures = vec![];
+        let status = output.status.code().unwrap_or(-1);
+        if status != self.status {
+            failures.push(format!("exit status: expected {}, got {status}", self.status));
+        }
+        let streams =
+            [("stdout", &self.stdout, &output.stdout), ("stderr", &self.stderr, &output.stderr)];
+        for (label, expected, actual) in streams {
+            if let Some(expected) = expected {
+                let actual = String::from_utf8_lossy(actual);
+                let header = (format!("expected {label}"), format!("actual {label}"));
+                if let Some(diff) = unified_diff(expected, &actual, (&header.0, &header.1)) {
+                    failures.push(diff);
+                }
+            }
+        }
+        Ok(failures)
+    }
+}
+
+impl CliCommand for TestCmd {
+    fn run(&self) -> CliResult {
+        tips::debug("Running tests");
+
+        let cases = self.collect_cases()?;
+        if cases.is_empty() {
+            tips::warning(&format!("No test cases found in {}", CONFIG.tests_dir().display()));
+            return Ok(());
+        }
+        tips::h1(&format!("Running {} test cases", cases.len()));
+
+        let pool = ThreadPool::new(self.jobs.max(1));
+        let (tx, rx) = mpsc::channel();
+        for (name, path) in cases {
+            let tx = tx.clone();
+            pool.execute(move || {
+                let failures = match run_case(&path) {
+                    Ok(failures) => failures,
+                    Err(e) => vec![format!("{e:#}")],
+                };
+                tx.send(TestResult { name, failures }).expect("failed to send test result");
+            });
+        }
+        drop(tx);
+
+        let mut failed = vec![];
+        let mut passed = 0;
+        for result in rx {
+            if result.failures.is_empty() {
+                println!("test {} ... {}", result.name, "ok".green());
+                passed += 1;
+            } else {
+                println!("test {} ... {}", result.name, "FAILED".red());
+                failed.push(result);
+            }
+        }
+
+        for result in &failed {
+            tips::h1(&format!("Failures of {}", result.name));
+            for failure in &result.failures {
+                println!("{}", failure.trim_end());
+            }
+        }
+        println!("\ntest result: {} passed; {} failed", passed, failed.len());
+        if !failed.is_empty() {
+            anyhow::bail!("{} test case(s) failed", failed.len());
+        }
+
+        Ok(())
+    }
+}
+
+impl TestCmd {
+    /// Returns the test cases, as `(name, path)` pairs sorted by name.
+    fn collect_cases(&self) -> anyhow::Result<Vec<(String, PathBuf)>> {
+        let tests_dir = CONFIG.tests_dir();
+        if !tests_dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut cases = vec![];
+        for entry in WalkDir::new(&tests_dir).sort_by_file_name() {
+            let path = entry?.into_path();
+            let is_case = path
+                .extension()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| matches!(x, "toml" | "yaml" | "yml"));
+            if !path.is_file() || !is_case {
+                continue;
+            }
+            let name = path.strip_prefix(&tests_dir)?.with_extension("").display().to_string();
+            if self.filter.as_ref().is_none_or(|f| name.contains(f.as_str())) {
+                cases.push((name, path));
+            }
+        }
+        Ok(cases)
+    }
+}
+
+fn run_case(path: &Path) -> anyhow::Result<Vec<String>> {
+    let case = TestCase::load(path)
+        .with_context(|| format!("failed to load test case '{}'", path.display()))?;
+    let bin_file = case.bin_file()?;
+    case.run(&bin_file)
+}
@@ -0,0 +1,56 @@
+use anyhow::Context;
+
+use crate::prelude::*;
+use crate::utils::fs::read_lines;
+use crate::utils::sourcemap::SourceMap;
+use crate::utils::tips;
+
+/// Resolve a line of a built script back to its source.
+#[derive(clap::Parser, Debug)]
+pub struct TraceCmd {
+    /// Bin name
+    bin: String,
+    /// Line number in the built script
+    line: usize,
+}
+
+impl CliCommand for TraceCmd {
+    fn run(&self) -> CliResult {
+        let path = CONFIG.sourcemap_file(&self.bin);
+        if !path.is_file() {
+            anyhow::bail!(
+                "source map not found: {}, run `shinc build` to generate it",
+                path.display()
+            );
+        }
+        let sourcemap = SourceMap::load(&path)
+            .with_context(|| format!("failed to load source map at '{}'", path.display()))?;
+        if self.line == 0 || self.line > sourcemap.len() {
+            anyhow::bail!(
+                "line {} is out of range, '{}' has {} lines",
+                self.line,
+                sourcemap.file(),
+                sourcemap.len()
+            );
+        }
+
+        match sourcemap.lookup(self.line) {
+            Some(location) => {
+                println!("{}:{}", location.source, location.line);
+                let source_file = CONFIG.resolve_root_path(location.source);
+                if let Some(Ok(text)) =
+                    read_lines(source_file).ok().and_then(|mut x| x.nth(location.line - 1))
+                {
+                    println!("{:>6} | {}", location.line, text);
+                }
+            }
+            None => tips::warning(&format!(
+                "line {} of '{}' has no source (generated by argc or blank)",
+                self.line,
+                sourcemap.file()
+            )),
+        }
+
+        Ok(())
+    }
+}
@@ -1,12 +1,17 @@
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
 
 use once_cell::sync::Lazy;
 
 use crate::de::{deserialize_pathbuf, deserialize_pathbuf_option};
-use crate::include_template;
-
-/// The default configuration template.
-pub const DEFAULT_CONFIG: &str = include_template!("config/default.toml");
+use crate::utils::archive::ArchiveFormat;
+use crate::utils::hashlib::HashAlgorithm;
+use crate::utils::include::IncludeRule;
+use crate::utils::sign::SignMode;
+use crate::utils::template;
+
+/// The name of the default configuration template.
+pub const DEFAULT_CONFIG_NAME: &str = "config/default.toml";
 /// The sub-directory where local or user configuration files are stored.
 pub const CONFIG_SUBDIR: &str = concat!(".config/", clap::crate_name!());
 /// The name of the configuration file.
@@ -20,6 +25,7 @@ pub struct Config {
     bin: Option<Vec<Bin>>,
     build: Option<Build>,
     dist: Dist,
+    package: Package,
     tools: Tools,
     release: Release,
 }
@@ -65,11 +71,59 @@ pub struct Build {
 pub struct Dist {
     name: Option<String>,
     include_extra: Vec<String>,
+    /// The top-level directory of the archives, rendered with the `name` and
+    /// `version` variables, e.g. `{{ name }}-v{{ version }}`.
+    prefix: Option<String>,
+    formats: Vec<ArchiveFormat>,
+    checksums: Vec<HashAlgorithm>,
+    sign: Option<Sign>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct Sign {
+    /// Sign with a minisign key, or with an SSH key through `ssh-keygen`.
+    mode: SignMode,
+    /// The secret key file.
+    #[serde(default, deserialize_with = "deserialize_pathbuf_option")]
+    key: Option<PathBuf>,
+    /// An environment variable holding the secret key, used instead of `key`
+    /// when it is set.
+    key_env: Option<String>,
+    /// The public key checked by `shinc dist verify` and `install.sh`.
+    public_key: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct Package {
+    /// The revision of the packages, appended to the project version.
+    release: String,
+    /// Defaults to the `user.name` and `user.email` of git.
+    maintainer: Option<String>,
+    deb: DebPackage,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct DebPackage {
+    /// The Debian packages providing the tools of `@meta require-tools`,
+    /// mapping a tool to `""` leaves it out of the dependencies.
+    tools: BTreeMap<String, String>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct Tools {
+    /// The formatter of the built scripts, defaults to `shfmt` if installed
+    /// and to the built-in formatter otherwise.
+    formatter: Option<ShellFormatter>,
     shfmt: Shfmt,
+    shellcheck: Shellcheck,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellFormatter {
+    Shfmt,
+    Builtin,
+    None,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -77,9 +131,47 @@ pub struct Shfmt {
     options: Vec<String>,
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct Shellcheck {
+    /// Run shellcheck on the compiled scripts during `shinc build`.
+    enabled: bool,
+    /// The minimum severity of the findings to report.
+    severity: ShellcheckSeverity,
+    /// Codes of the checks to skip, e.g. `SC2034`.
+    exclude: Vec<String>,
+    /// Whether findings below `error` fail the build.
+    deny_warnings: bool,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellcheckSeverity {
+    Error,
+    Warning,
+    Info,
+    Style,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct Release {
     changelog: String,
+    /// The branches to release from, names or globs, any if empty.
+    branches: Vec<String>,
+    /// Refuse to release with uncommitted changes to tracked files.
+    check_clean: bool,
+    /// Refuse to release a branch behind its upstream.
+    check_upstream: bool,
+    hooks: ReleaseHooks,
+}
+
+/// The commands run by `shinc release` around each of its steps.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct ReleaseHooks {
+    pre_bump: Vec<String>,
+    post_bump: Vec<String>,
+    pre_commit: Vec<String>,
+    post_tag: Vec<String>,
+    post_push: Vec<String>,
 }
 
 #[allow(dead_code)]
@@ -92,14 +184,103 @@ impl Project {
         &self.version
     }
 
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn homepage(&self) -> Option<&url::Url> {
+        self.homepage.as_ref()
+    }
+
     pub fn repository(&self) -> Option<&url::Url> {
         self.repository.as_ref()
     }
+
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+}
+
+impl Sign {
+    pub fn mode(&self) -> SignMode {
+        self.mode
+    }
+
+    pub fn key(&self) -> Option<&Path> {
+        self.key.as_deref()
+    }
+
+    pub fn key_env(&self) -> Option<&str> {
+        self.key_env.as_deref()
+    }
+
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+}
+
+impl Shellcheck {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn severity(&self) -> ShellcheckSeverity {
+        self.severity
+    }
+
+    pub fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    pub fn deny_warnings(&self) -> bool {
+        self.deny_warnings
+    }
+}
+
+impl ReleaseHooks {
+    pub fn pre_bump(&self) -> &[String] {
+        &self.pre_bump
+    }
+
+    pub fn post_bump(&self) -> &[String] {
+        &self.post_bump
+    }
+
+    pub fn pre_commit(&self) -> &[String] {
+        &self.pre_commit
+    }
+
+    pub fn post_tag(&self) -> &[String] {
+        &self.post_tag
+    }
+
+    pub fn post_push(&self) -> &[String] {
+        &self.post_push
+    }
+}
+
+impl ShellcheckSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+            Self::Style => "style",
+        }
+    }
 }
 
 impl Config {
     pub fn new<P: AsRef<Path>>(root: P) -> anyhow::Result<Self> {
-        let sources = vec![::config::File::from_str(DEFAULT_CONFIG, ::config::FileFormat::Toml)];
+        let default = template::embedded(DEFAULT_CONFIG_NAME).unwrap_or_default();
+        let sources = vec![::config::File::from_str(default, ::config::FileFormat::Toml)];
+        // An overridden default configuration is layered over the embedded
+        // one, so that it only needs the keys it changes.
+        let overrides: Vec<_> =
+            template::find_override(&template::override_dirs(&root), DEFAULT_CONFIG_NAME)
+                .into_iter()
+                .map(::config::File::from)
+                .collect();
         let files: Vec<_> = [Self::locate_config_path(&root)]
             .iter()
             .rev()
@@ -111,6 +292,7 @@ impl Config {
         }
         let mut cfg = ::config::Config::builder()
             .add_source(sources)
+            .add_source(overrides)
             .add_source(files)
             .build()?
             .try_deserialize::<Self>()?;
@@ -132,6 +314,10 @@ impl Config {
         Self::locate_config_path(&self.root)
     }
 
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
     pub fn project(&self) -> &Project {
         &self.project
     }
@@ -175,6 +361,14 @@ impl Config {
         self.resolve_src_path("main.sh")
     }
 
+    pub fn tests_dir(&self) -> PathBuf {
+        self.resolve_root_path("tests")
+    }
+
+    pub fn snapshots_dir(&self) -> PathBuf {
+        self.resolve_root_path("snapshots")
+    }
+
     pub fn target_dir(&self) -> PathBuf {
         self.build
             .as_ref()
@@ -198,6 +392,14 @@ impl Config {
         self.build_dir().join(name)
     }
 
+    pub fn sourcemap_file(&self, name: &str) -> PathBuf {
+        self.build_file(&format!("{name}.sh.map"))
+    }
+
+    pub fn build_manifest_file(&self) -> PathBuf {
+        self.build_file("manifest.json")
+    }
+
     pub fn share_dir(&self) -> PathBuf {
         self.target_dir().join("share")
     }
@@ -247,17 +449,110 @@ impl Config {
         self.dist.name.as_deref()
     }
 
-    pub fn dist_include_extra_paths(&self) -> Vec<PathBuf> {
-        self.dist.include_extra.iter().map(PathBuf::from).collect()
+    pub fn dist_formats(&self) -> &[ArchiveFormat] {
+        &self.dist.formats
+    }
+
+    pub fn dist_checksums(&self) -> &[HashAlgorithm] {
+        &self.dist.checksums
+    }
+
+    pub fn dist_sign(&self) -> Option<&Sign> {
+        self.dist.sign.as_ref()
+    }
+
+    /// Returns the filename of the distribution archive in `format`.
+    pub fn dist_archive_name(&self, format: ArchiveFormat) -> String {
+        format!(
+            "{}-v{}.{}",
+            self.inferred_dist_name(),
+            self.project().version(),
+            format.extension()
+        )
+    }
+
+    /// Returns the download URL of the archive in `format` from the GitHub
+    /// release of the current version, if `[project].repository` is set.
+    pub fn dist_archive_url(&self, format: ArchiveFormat) -> Option<String> {
+        self.project().repository().map(|repo| {
+            format!(
+                "{}/releases/download/v{}/{}",
+                repo.as_str().trim_end_matches('/'),
+                self.project().version(),
+                self.dist_archive_name(format)
+            )
+        })
+    }
+
+    /// Returns the top-level directory of the archives, if any.
+    pub fn dist_prefix(&self) -> anyhow::Result<Option<PathBuf>> {
+        let Some(template) = self.dist.prefix.as_deref().filter(|x| !x.is_empty()) else {
+            return Ok(None);
+        };
+        let mut ctx = tera::Context::new();
+        ctx.insert("name", &self.inferred_dist_name());
+        ctx.insert("version", self.project().version());
+        let prefix = PathBuf::from(tera::Tera::one_off(template, &ctx, false)?.trim());
+        if !prefix.components().all(|c| matches!(c, Component::Normal(_))) {
+            anyhow::bail!("`[dist].prefix` must be a relative path, got '{}'", prefix.display());
+        }
+        Ok(Some(prefix))
+    }
+
+    /// Returns the `[dist].include_extra` rules, see [`IncludeRule`].
+    pub fn dist_include_extra(&self) -> anyhow::Result<Vec<IncludeRule>> {
+        self.dist.include_extra.iter().map(|x| IncludeRule::parse(x)).collect()
+    }
+
+    pub fn package_dir(&self) -> PathBuf {
+        self.target_dir().join("package")
+    }
+
+    pub fn package_release(&self) -> &str {
+        &self.package.release
+    }
+
+    pub fn package_maintainer(&self) -> Option<&str> {
+        self.package.maintainer.as_deref()
+    }
+
+    /// Returns the Debian package providing `tool`, if it is mapped in
+    /// `[package.deb].tools`.
+    pub fn deb_package(&self, tool: &str) -> Option<&str> {
+        self.package.deb.tools.get(tool).map(String::as_str)
+    }
+
+    pub fn formatter(&self) -> Option<ShellFormatter> {
+        self.tools.formatter
     }
 
     pub fn shfmt_options(&self) -> Vec<&str> {
         self.tools.shfmt.options.iter().map(String::as_str).collect()
     }
 
+    pub fn shellcheck(&self) -> &Shellcheck {
+        &self.tools.shellcheck
+    }
+
     pub fn changelog(&self) -> &str {
         &self.release.changelog
     }
+
+    pub fn release_branches(&self) -> &[String] {
+        &self.release.branches
+    }
+
+    pub fn release_check_clean(&self) -> bool {
+        self.release.check_clean
+    }
+
+    pub fn release_check_upstream(&self) -> bool {
+        self.release.check_upstream
+    }
+
+    pub fn release_hooks(&self) -> &ReleaseHooks {
+        &self.release.hooks
+    }
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,229 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use xz2::write::XzEncoder;
+use zip::write::SimpleFileOptions;
+
+use crate::utils::fs::create_file;
+
+/// The compression level used for `.tar.xz` archives.
+const XZ_LEVEL: u32 = 6;
+/// The compression level used for `.tar.zst` archives, `0` is zstd's default.
+const ZSTD_LEVEL: i32 = 0;
+
+#[derive(
+    serde::Deserialize,
+    serde::Serialize,
+    clap::ValueEnum,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum ArchiveFormat {
+    #[serde(rename = "tar.gz")]
+    #[value(name = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar.xz")]
+    #[value(name = "tar.xz")]
+    TarXz,
+    #[serde(rename = "tar.zst")]
+    #[value(name = "tar.zst")]
+    TarZst,
+    #[serde(rename = "zip")]
+    #[value(name = "zip")]
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::TarXz => "tar.xz",
+            Self::TarZst => "tar.zst",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+/// A file to add to an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path of the file on disk.
+    pub source: PathBuf,
+    /// Path of the file inside the archive.
+    pub path: PathBuf,
+}
+
+/// Writes `entries` to a new archive at `output`.
+///
+/// The archive only depends on the content of the entries: they are sorted by
+/// path, owned by root, have their mode normalized to `0644` or `0755` and
+/// their mtime set to `mtime` (seconds since the Unix epoch).
+pub fn create_archive<P: AsRef<Path>>(
+    output: P,
+    format: ArchiveFormat,
+    entries: &[ArchiveEntry],
+    mtime: u64,
+) -> anyhow::Result<()> {
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    let fp = create_file(output)?;
+    match format {
+        ArchiveFormat::TarGz => {
+            // The gzip header has no mtime or filename by default.
+            write_tar(GzEncoder::new(fp, Compression::default()), &entries, mtime)?.finish()?;
+        }
+        ArchiveFormat::TarXz => {
+            write_tar(XzEncoder::new(fp, XZ_LEVEL), &entries, mtime)?.finish()?;
+        }
+        ArchiveFormat::TarZst => {
+            write_tar(zstd::Encoder::new(fp, ZSTD_LEVEL)?, &entries, mtime)?.finish()?;
+        }
+        ArchiveFormat::Zip => write_zip(fp, &entries, mtime)?,
+    }
+    Ok(())
+}
+
+fn write_tar<W: Write>(writer: W, entries: &[&ArchiveEntry], mtime: u64) -> io::Result<W> {
+    let mut tar = tar::Builder::new(writer);
+    for entry in entries {
+        let fp = fs::File::open(&entry.source)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(fp.metadata()?.len());
+        header.set_mode(file_mode(&entry.source)?);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_username("root")?;
+        header.set_groupname("root")?;
+        header.set_mtime(mtime);
+        tar.append_data(&mut header, &entry.path, fp)?;
+    }
+    tar.into_inner()
+}
+
+fn write_zip(fp: fs::File, entries: &[&ArchiveEntry], mtime: u64) -> anyhow::Result<()> {
+    let mut zip = zip::ZipWriter::new(fp);
+    let modified = zip_datetime(mtime);
+    for entry in entries {
+        let options = SimpleFileOptions::default()
+            .unix_permissions(file_mode(&entry.source)?)
+            .last_modified_time(modified);
+        zip.start_file_from_path(&entry.path, options)?;
+        io::copy(&mut fs::File::open(&entry.source)?, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Converts a Unix timestamp to a zip timestamp, which cannot be earlier
+/// than 1980.
+fn zip_datetime(mtime: u64) -> zip::DateTime {
+    use chrono::{Datelike, Timelike};
+
+    let time = i64::try_from(mtime)
+        .ok()
+        .and_then(|x| chrono::DateTime::from_timestamp(x, 0))
+        .unwrap_or_default();
+    u16::try_from(time.year())
+        .ok()
+        .and_then(|year| {
+            zip::DateTime::from_date_and_time(
+                year,
+                time.month() as u8,
+                time.day() as u8,
+                time.hour() as u8,
+                time.minute() as u8,
+                time.second() as u8,
+            )
+            .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns `0755` for executable files and `0644` for the others.
+#[cfg(unix)]
+fn file_mode(path: &Path) -> io::Result<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path)?.permissions().mode();
+    Ok(if mode & 0o111 != 0 { 0o755 } else { 0o644 })
+}
+
+#[cfg(not(unix))]
+fn file_mode(_path: &Path) -> io::Result<u32> {
+    Ok(0o644)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_create_archive() {
+        let temp_dir = std::env::temp_dir().join("test_create_archive");
+        let source = temp_dir.join("hello.txt");
+        create_file(&source).unwrap().write_all(b"hello").unwrap();
+        let entries = [ArchiveEntry { source, path: PathBuf::from("bin/hello.txt") }];
+
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::TarXz, ArchiveFormat::TarZst] {
+            let output = temp_dir.join(format!("out.{}", format.extension()));
+            create_archive(&output, format, &entries, 0).unwrap();
+            let fp = fs::File::open(&output).unwrap();
+            let reader: Box<dyn Read> = match format {
+                ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(fp)),
+                ArchiveFormat::TarXz => Box::new(xz2::read::XzDecoder::new(fp)),
+                _ => Box::new(zstd::Decoder::new(fp).unwrap()),
+            };
+            let mut tar = tar::Archive::new(reader);
+            let mut entry = tar.entries().unwrap().next().unwrap().unwrap();
+            assert_eq!(entry.path().unwrap(), Path::new("bin/hello.txt"));
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            assert_eq!(content, "hello");
+        }
+
+        let output = temp_dir.join("out.zip");
+        create_archive(&output, ArchiveFormat::Zip, &entries, 0).unwrap();
+        let mut zip = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+        let mut content = String::new();
+        zip.by_name("bin/hello.txt").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "hello");
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_archive_is_reproducible() {
+        let temp_dir = std::env::temp_dir().join("test_create_archive_is_reproducible");
+        let mut entries = vec![];
+        for name in ["b.txt", "a.txt"] {
+            let source = temp_dir.join("src").join(name);
+            create_file(&source).unwrap().write_all(name.as_bytes()).unwrap();
+            entries.push(ArchiveEntry { source, path: PathBuf::from(name) });
+        }
+
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            let first = temp_dir.join(format!("first.{}", format.extension()));
+            let second = temp_dir.join(format!("second.{}", format.extension()));
+            create_archive(&first, format, &entries, 1_700_000_000).unwrap();
+            // Touch the sources and list them in another order.
+            for entry in &entries {
+                fs::write(&entry.source, fs::read(&entry.source).unwrap()).unwrap();
+            }
+            entries.reverse();
+            create_archive(&second, format, &entries, 1_700_000_000).unwrap();
+            assert_eq!(fs::read(first).unwrap(), fs::read(second).unwrap());
+        }
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use colored::Colorize;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::parser::{Event, EventData};
+use super::tag;
+
+/// Matches the `argc_*` variables read by a line, e.g. `$argc_name`,
+/// `${argc_name}` or `${#argc_name[@]}`.
+static VAR_READ: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$\{?[#!]?(argc_\w+)").unwrap());
+
+/// Matches the `argc_*` variables assigned or declared by a line.
+static VAR_DECLARE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?:^|[\s;])(?:(?:local|declare|typeset|readonly|export)(?:\s+-\w+)*\s+)?(argc_\w+)(?:\[[^\]]*\])?(?:\+?=|\s*$|\s*;)",
+    )
+    .unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found at a line of a source file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning".yellow(),
+            Severity::Error => "error".red(),
+        };
+        write!(f, "{}:{}: {severity}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// Checks the argc annotations of a bin.
+///
+/// The events of the entry file and its includes are fed in the order they
+/// are inlined, then [`Linter::finish`] runs the checks that need the whole
+/// script.
+#[derive(Debug, Default)]
+pub struct Linter {
+    diagnostics: Vec<Diagnostic>,
+    /// Flags and options of the current command, with where they are defined.
+    names: HashMap<String, (String, usize)>,
+    /// `argc_*` variables generated by tags or assigned by the script.
+    declared: HashSet<String>,
+    /// `argc_*` variables read by the script, with where they are read.
+    reads: Vec<(String, String, usize)>,
+    described: bool,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&mut self, file: &str, line: usize, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic { file: file.to_string(), line, severity, message });
+    }
+
+    /// Checks an event parsed from `file`.
+    pub fn check(&mut self, file: &str, event: &Event) {
+        let line = event.position;
+        match &event.data {
+            EventData::Meta(key, _) => {
+                if !tag::META_KEYS.contains(&key.as_str()) {
+                    let message = format!("unknown @meta key '{key}'");
+                    self.report(file, line, Severity::Warning, message);
+                }
+            }
+            EventData::Include(_) => {}
+            EventData::Unknown(text) => match text.strip_prefix("# @") {
+                Some(text) => self.check_tag(file, line, text),
+                None => self.check_code(file, line, text),
+            },
+        }
+    }
+
+    /// Runs the checks that need the whole script, and returns every problem
+    /// found. `entry` is the file the bin is built from.
+    pub fn finish(mut self, entry: &str) -> Vec<Diagnostic> {
+        if !self.described {
+            self.report(entry, 1, Severity::Warning, "missing @describe".to_string());
+        }
+        for (var, file, line) in std::mem::take(&mut self.reads) {
+            if !self.declared.contains(&var) {
+                let message = format!("'{var}' is read but never declared");
+                self.report(&file, line, Severity::Error, message);
+            }
+        }
+        self.diagnostics
+    }
+
+    fn check_tag(&mut self, file: &str, line: usize, text: &str) {
+        let (name, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let rest = rest.trim();
+        match name {
+            tag::DESCRIBE => self.described = true,
+            tag::CMD => self.names.clear(),
+            tag::FLAG | tag::OPTION => self.check_flag_option(file, line, name, rest),
+            tag::ARG => {
+                if let Some(var) = rest.split_whitespace().next().map(param_name) {
+                    self.declare(var);
+                }
+            }
+            _ if tag::TAGS.contains(&name) => {}
+            _ => self.report(file, line, Severity::Error, format!("unknown tag '@{name}'")),
+        }
+    }
+
+    fn check_flag_option(&mut self, file: &str, line: usize, kind: &str, text: &str) {
+        let names: Vec<_> = text
+            .split_whitespace()
+            .take_while(|x| x.starts_with('-'))
+            .map(|x| {
+                let dashes = x.len() - x.trim_start_matches('-').len();
+                format!("{}{}", &x[..dashes], param_name(&x[dashes..]))
+            })
+            .filter(|x| !x.trim_start_matches('-').is_empty())
+            .collect();
+        if let Some(var) = names.last() {
+            self.declare(var.trim_start_matches('-'));
+        }
+        for name in names {
+            if let Some((first_file, first_line)) = self.names.get(&name) {
+                let message = format!(
+                    "duplicate @{kind} '{name}', first defined at {first_file}:{first_line}"
+                );
+                self.report(file, line, Severity::Error, message);
+            } else {
+                self.names.insert(name, (file.to_string(), line));
+            }
+        }
+    }
+
+    fn check_code(&mut self, file: &str, line: usize, text: &str) {
+        if text.trim_start().starts_with('#') {
+            return;
+        }
+        for caps in VAR_DECLARE.captures_iter(text) {
+            self.declared.insert(caps[1].to_string());
+        }
+        for caps in VAR_READ.captures_iter(text) {
+            let var = &caps[1];
+            // Variables like `argc__args` are always set by argc.
+            if !var.starts_with("argc__") {
+                self.reads.push((var.to_string(), file.to_string(), line));
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &str) {
+        self.declared.insert(format!("argc_{}", name.replace('-', "_")));
+    }
+}
+
+/// Strips the modifiers, default value and choices from a parameter name,
+/// e.g. `name*[a|b]` becomes `name`.
+fn param_name(param: &str) -> &str {
+    let end = param
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')))
+        .unwrap_or(param.len());
+    &param[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::argc::parser::parse;
+
+    fn lint(source: &str) -> Vec<(usize, Severity, String)> {
+        let mut linter = Linter::new();
+        for event in parse(source).unwrap() {
+            linter.check("main.sh", &event);
+        }
+        linter.finish("main.sh").into_iter().map(|d| (d.line, d.severity, d.message)).collect()
+    }
+
+    #[test]
+    fn test_lint_valid_script() {
+        let source = r#"# @describe A tool
+# @meta version 1.0.0
+# @flag -f --force-all Force it
+# @option -o --output <FILE> Output file
+# @arg name! Name
+
+# @cmd Sub command
+# @flag -f --force-all Force it
+sub() {
+    local argc_extra=1
+    echo "$argc_force_all ${argc_output:-} ${argc_name} $argc_extra ${argc__args[@]}"
+}
+eval "$(argc --argc-eval "$0" "$@")"
+"#;
+        assert_eq!(lint(source), vec![]);
+    }
+
+    #[test]
+    fn test_lint_reports_problems() {
+        let source = r#"# @meta colour always
+# @flg --force
+# @flag -f --force
+# @option -f --file <FILE>
+echo "$argc_force $argc_missing"
+"#;
+        assert_eq!(
+            lint(source),
+            vec![
+                (1, Severity::Warning, "unknown @meta key 'colour'".to_string()),
+                (2, Severity::Error, "unknown tag '@flg'".to_string()),
+                (
+                    4,
+                    Severity::Error,
+                    "duplicate @option '-f', first defined at main.sh:3".to_string()
+                ),
+                (1, Severity::Warning, "missing @describe".to_string()),
+                (5, Severity::Error, "'argc_missing' is read but never declared".to_string()),
+            ]
+        );
+    }
+}
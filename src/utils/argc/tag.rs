@@ -1,4 +1,10 @@
 pub const DESCRIBE: &str = "describe";
+pub const CMD: &str = "cmd";
+pub const ALIAS: &str = "alias";
+pub const ARG: &str = "arg";
+pub const OPTION: &str = "option";
+pub const FLAG: &str = "flag";
+pub const ENV: &str = "env";
 pub const META: &str = "meta";
 pub const META_VERSION: &str = "version";
 pub const META_AUTHOR: &str = "author";
@@ -13,6 +19,22 @@ pub const META_REQUIRE_TOOLS: &str = "require-tools";
 // Custom tag
 pub const INCLUDE: &str = "include";
 
+/// Every tag understood by `argc` or `shinc`.
+pub const TAGS: [&str; 9] = [DESCRIBE, CMD, ALIAS, ARG, OPTION, FLAG, ENV, META, INCLUDE];
+
+/// Every key understood by `@meta`.
+pub const META_KEYS: [&str; 9] = [
+    META_VERSION,
+    META_AUTHOR,
+    META_DOTENV,
+    META_DEFAULT_SUBCOMMAND,
+    META_INHERIT_FLAG_OPTIONS,
+    META_SYMBOL,
+    META_COMBINE_SHORTS,
+    META_MAN_SECTION,
+    META_REQUIRE_TOOLS,
+];
+
 pub struct ArgcTag;
 
 #[allow(dead_code)]
@@ -0,0 +1,32 @@
+use similar::TextDiff;
+
+/// Returns a unified diff between `expected` and `actual`, or `None` if they
+/// are equal.
+#[allow(dead_code)]
+pub fn unified_diff(expected: &str, actual: &str, header: (&str, &str)) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let diff = TextDiff::from_lines(expected, actual)
+        .unified_diff()
+        .context_radius(3)
+        .header(header.0, header.1)
+        .to_string();
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_equal() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", ("expected", "actual")), None);
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("a\nb\n", "a\nc\n", ("expected", "actual")).unwrap();
+        assert_eq!(diff, "--- expected\n+++ actual\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n");
+    }
+}
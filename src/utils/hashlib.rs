@@ -2,20 +2,70 @@ use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
 
-use sha2::{Digest, Sha256};
+use sha2::digest::{Digest, Output};
+use sha2::{Sha256, Sha512};
 
 const BUFFER_SIZE: usize = 8192;
 
-struct Sha256Hasher<R> {
-    inner: Sha256,
+/// The hash algorithms of the checksum manifests.
+#[derive(
+    serde::Deserialize,
+    serde::Serialize,
+    clap::ValueEnum,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Returns the filename of the checksum manifest, named after the
+    /// coreutils and b3sum conventions.
+    pub fn manifest_name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "SHA256SUMS",
+            Self::Sha512 => "SHA512SUMS",
+            Self::Blake3 => "B3SUMS",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Returns the hex digest of everything read from `reader`.
+    pub fn hex<R: Read>(&self, reader: R) -> anyhow::Result<String> {
+        match self {
+            Self::Sha256 => Hasher::<Sha256, _>::new(reader).hex(),
+            Self::Sha512 => Hasher::<Sha512, _>::new(reader).hex(),
+            Self::Blake3 => Hasher::<blake3::Hasher, _>::new(reader).hex(),
+        }
+    }
+}
+
+struct Hasher<D, R> {
+    inner: D,
     reader: R,
     buffer_size: usize,
 }
 
 #[allow(dead_code)]
-impl<R: Read> Sha256Hasher<R> {
+impl<D: Digest, R: Read> Hasher<D, R> {
     pub fn new(reader: R) -> Self {
-        Self { inner: Sha256::new(), reader, buffer_size: BUFFER_SIZE }
+        Self { inner: D::new(), reader, buffer_size: BUFFER_SIZE }
     }
 
     pub fn buffer_size(mut self, n: usize) -> Self {
@@ -23,7 +73,7 @@ impl<R: Read> Sha256Hasher<R> {
         self
     }
 
-    pub fn calculate(mut self) -> anyhow::Result<sha2::digest::Output<Sha256>> {
+    pub fn calculate(mut self) -> anyhow::Result<Output<D>> {
         let mut buffer = vec![0; self.buffer_size];
         loop {
             let n = self.reader.read(&mut buffer)?;
@@ -40,14 +90,76 @@ impl<R: Read> Sha256Hasher<R> {
     }
 }
 
-pub fn calculate_file_sha256<P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
+pub fn calculate_file_hash<P: AsRef<Path>>(
+    path: P,
+    algorithm: HashAlgorithm,
+) -> anyhow::Result<String> {
     let file = File::open(path)?;
-    let buf = BufReader::new(file);
-    Sha256Hasher::new(buf).hex()
+    algorithm.hex(BufReader::new(file))
+}
+
+pub fn calculate_file_sha256<P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
+    calculate_file_hash(path, HashAlgorithm::Sha256)
 }
 
 pub fn calculate_url_sha256(url: &str) -> anyhow::Result<String> {
     let client = reqwest::blocking::Client::new();
     let response = client.get(url).send()?;
-    Sha256Hasher::new(response).hex()
+    HashAlgorithm::Sha256.hex(response)
+}
+
+/// Formats a checksum manifest line the way `sha256sum` and friends do, so
+/// that the manifest can be checked with `sha256sum -c`.
+pub fn format_checksum_line(checksum: &str, filename: &str) -> String {
+    format!("{checksum}  {filename}")
+}
+
+/// Parses a line written by [`format_checksum_line`], the binary mode marker
+/// `*` before the filename is also accepted.
+pub fn parse_checksum_line(line: &str) -> Option<(&str, &str)> {
+    let (checksum, filename) = line.split_once(' ')?;
+    let filename = filename.strip_prefix([' ', '*'])?;
+    if checksum.is_empty() || filename.is_empty() {
+        return None;
+    }
+    Some((checksum, filename))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_algorithms() {
+        let cases = [
+            (
+                HashAlgorithm::Sha256,
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f",
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            assert_eq!(algorithm.hex(&b"hello"[..]).unwrap(), expected);
+        }
+        // The result doesn't depend on how the input is split.
+        let small = Hasher::<Sha256, _>::new(&b"hello"[..]).buffer_size(2).hex().unwrap();
+        assert_eq!(small, cases[0].1);
+    }
+
+    #[test]
+    fn test_checksum_line() {
+        let line = format_checksum_line("abc", "a b.tar.gz");
+        assert_eq!(line, "abc  a b.tar.gz");
+        assert_eq!(parse_checksum_line(&line), Some(("abc", "a b.tar.gz")));
+        assert_eq!(parse_checksum_line("abc *a.zip"), Some(("abc", "a.zip")));
+        assert_eq!(parse_checksum_line("abc a.zip"), None);
+        assert_eq!(parse_checksum_line("abc"), None);
+    }
 }
@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use walkdir::WalkDir;
+
+use crate::utils::archive::ArchiveEntry;
+
+/// An entry of `[dist].include_extra`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncludeRule {
+    /// Adds the files matching `pattern`, a path or a glob relative to the
+    /// project root, e.g. `docs/**/*.md`. They are placed under `dest` if
+    /// set, e.g. `LICENSE -> share/doc/name/LICENSE`. An `optional` one, with
+    /// a `?` prefix, may match no file, e.g. `?README.md`.
+    Include { pattern: String, dest: Option<PathBuf>, optional: bool },
+    /// Leaves out the files matching a glob, e.g. `!docs/internal/**`.
+    Exclude(String),
+}
+
+impl IncludeRule {
+    pub fn parse(rule: &str) -> anyhow::Result<Self> {
+        let rule = rule.trim();
+        if let Some(pattern) = rule.strip_prefix('!') {
+            if pattern.contains("->") {
+                anyhow::bail!("an exclusion cannot be renamed: '{rule}'");
+            }
+            return Ok(Self::Exclude(pattern.trim().to_string()));
+        }
+        let (rule, optional) = match rule.strip_prefix('?') {
+            Some(rule) => (rule.trim(), true),
+            None => (rule, false),
+        };
+        let (pattern, dest) = match rule.split_once("->") {
+            Some((pattern, dest)) => (pattern.trim(), Some(dest.trim())),
+            None => (rule, None),
+        };
+        if pattern.is_empty() || dest.is_some_and(str::is_empty) {
+            anyhow::bail!("invalid include rule: '{rule}'");
+        }
+        let dest = dest.map(PathBuf::from);
+        if let Some(dest) = &dest
+            && !is_relative_inside(dest)
+        {
+            anyhow::bail!("the destination of '{rule}' must be a relative path inside the archive");
+        }
+        Ok(Self::Include { pattern: pattern.to_string(), dest, optional })
+    }
+}
+
+/// Lists the files selected by `rules`, relative to `root`.
+///
+/// Every include but the optional ones must match at least one file, before
+/// exclusions are applied. Two different files cannot be placed at the same
+/// path.
+pub fn resolve_includes(root: &Path, rules: &[IncludeRule]) -> anyhow::Result<Vec<ArchiveEntry>> {
+    let mut builder = GlobSetBuilder::new();
+    for rule in rules {
+        if let IncludeRule::Exclude(pattern) = rule {
+            builder.add(glob(pattern)?);
+        }
+    }
+    let excludes = builder.build()?;
+
+    let mut entries: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+    for rule in rules {
+        let IncludeRule::Include { pattern, dest, optional } = rule else {
+            continue;
+        };
+        let matches = match_files(root, pattern, dest.as_deref())?;
+        if matches.is_empty() {
+            if *optional {
+                log::debug!("Skipping the optional include '{pattern}', which matches no file");
+                continue;
+            }
+            if is_glob(pattern) {
+                anyhow::bail!("`[dist].include_extra` entry '{pattern}' matches no file");
+            }
+            anyhow::bail!("`[dist].include_extra` entry '{pattern}' does not exist");
+        }
+        for (relative, path) in matches {
+            if is_excluded(&excludes, &relative) {
+                continue;
+            }
+            if !is_relative_inside(&path) {
+                anyhow::bail!(
+                    "'{}' is outside the project, place it in the archive with `{pattern} -> <path>`",
+                    relative.display()
+                );
+            }
+            let source = root.join(&relative);
+            match entries.get(&path) {
+                Some(other) if other != &source => anyhow::bail!(
+                    "'{}' and '{}' are both placed at '{}' in the archive",
+                    other.display(),
+                    source.display(),
+                    path.display()
+                ),
+                _ => entries.insert(path, source),
+            };
+        }
+    }
+    Ok(entries.into_iter().map(|(path, source)| ArchiveEntry { source, path }).collect())
+}
+
+/// Returns the files matching `pattern` relative to `root`, with their path
+/// in the archive.
+fn match_files(
+    root: &Path,
+    pattern: &str,
+    dest: Option<&Path>,
+) -> anyhow::Result<Vec<(PathBuf, PathBuf)>> {
+    let base = glob_base(pattern);
+    let matcher = if is_glob(pattern) { Some(glob(pattern)?.compile_matcher()) } else { None };
+    let start = root.join(&base);
+    if !start.exists() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in WalkDir::new(&start).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path()).to_path_buf();
+        if matcher.as_ref().is_some_and(|m| !m.is_match(&relative)) {
+            continue;
+        }
+        let inner = entry.path().strip_prefix(&start)?;
+        let path = match dest {
+            // A single file is renamed, a directory or glob is moved.
+            Some(dest) if inner.as_os_str().is_empty() => dest.to_path_buf(),
+            Some(dest) => dest.join(inner),
+            None => relative.clone(),
+        };
+        files.push((relative, path));
+    }
+    Ok(files)
+}
+
+/// Returns the leading components of `pattern` without glob characters.
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[', '{']))
+        .collect()
+}
+
+fn is_glob(pattern: &str) -> bool {
+    glob_base(pattern).as_os_str() != pattern
+}
+
+fn glob(pattern: &str) -> anyhow::Result<Glob> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| anyhow::format_err!("invalid glob '{pattern}': {e}"))
+}
+
+/// Whether `path` or one of its parent directories matches `excludes`.
+fn is_excluded(excludes: &GlobSet, path: &Path) -> bool {
+    path.ancestors().any(|p| !p.as_os_str().is_empty() && excludes.is_match(p))
+}
+
+fn is_relative_inside(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn setup(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        for file in ["LICENSE", "docs/a.md", "docs/b.txt", "docs/guide/c.md", "docs/internal/d.md"]
+        {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+        root
+    }
+
+    fn resolve(root: &Path, rules: &[&str]) -> anyhow::Result<Vec<(String, String)>> {
+        let rules: Vec<_> = rules.iter().map(|x| IncludeRule::parse(x).unwrap()).collect();
+        Ok(resolve_includes(root, &rules)?
+            .into_iter()
+            .map(|e| {
+                let source = e.source.strip_prefix(root).unwrap().display().to_string();
+                (source, e.path.display().to_string())
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_parse_include_rule() {
+        assert_eq!(
+            IncludeRule::parse("LICENSE -> share/doc/x/LICENSE").unwrap(),
+            IncludeRule::Include {
+                pattern: "LICENSE".to_string(),
+                dest: Some(PathBuf::from("share/doc/x/LICENSE")),
+                optional: false,
+            }
+        );
+        assert_eq!(
+            IncludeRule::parse("?README.md").unwrap(),
+            IncludeRule::Include { pattern: "README.md".to_string(), dest: None, optional: true }
+        );
+        assert_eq!(
+            IncludeRule::parse("!docs/internal/**").unwrap(),
+            IncludeRule::Exclude("docs/internal/**".to_string())
+        );
+        assert!(IncludeRule::parse("!a -> b").is_err());
+        assert!(IncludeRule::parse("a -> ../b").is_err());
+        assert!(IncludeRule::parse("a -> ").is_err());
+    }
+
+    #[test]
+    fn test_resolve_includes() {
+        let root = setup("test_resolve_includes");
+        let pairs = |x: &[(&str, &str)]| -> Vec<(String, String)> {
+            x.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect()
+        };
+
+        assert_eq!(
+            resolve(
+                &root,
+                &["docs/**/*.md", "!docs/internal/**", "LICENSE -> share/doc/x/LICENSE"]
+            )
+            .unwrap(),
+            pairs(&[
+                ("docs/a.md", "docs/a.md"),
+                ("docs/guide/c.md", "docs/guide/c.md"),
+                ("LICENSE", "share/doc/x/LICENSE"),
+            ])
+        );
+        // A directory is added recursively, and can be moved.
+        assert_eq!(
+            resolve(&root, &["docs -> share/doc", "!docs/internal", "!**/*.txt"]).unwrap(),
+            pairs(&[("docs/a.md", "share/doc/a.md"), ("docs/guide/c.md", "share/doc/guide/c.md")])
+        );
+        // `*` doesn't match `/`.
+        assert_eq!(resolve(&root, &["docs/*.md"]).unwrap(), pairs(&[("docs/a.md", "docs/a.md")]));
+
+        assert!(resolve(&root, &["MISSING"]).is_err());
+        // An optional entry may match no file, like a default `README.md`.
+        assert_eq!(
+            resolve(&root, &["?LICENSE", "?README.md", "?docs/*.rs"]).unwrap(),
+            pairs(&[("LICENSE", "LICENSE")])
+        );
+        assert!(resolve(&root, &["docs/*.rs"]).is_err());
+        assert!(resolve(&root, &["LICENSE -> a", "docs/a.md -> a"]).is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
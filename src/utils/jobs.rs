@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, mpsc};
+
+use threadpool::ThreadPool;
+
+use super::tips;
+
+/// Runs `f` for every item on up to `jobs` threads, returning the items along
+/// with their results in their original order.
+///
+/// Output printed through [`tips`] is buffered per item and printed once the
+/// item and all the items before it are done, so that the logs of concurrent
+/// jobs do not interleave.
+pub fn run<T, R, F>(items: Vec<T>, jobs: usize, f: F) -> Vec<(T, anyhow::Result<R>)>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(&T) -> anyhow::Result<R> + Send + Sync + 'static,
+{
+    let total = items.len();
+    let pool = ThreadPool::new(jobs.clamp(1, total.max(1)));
+    let f = Arc::new(f);
+    let (tx, rx) = mpsc::channel();
+    for (idx, item) in items.into_iter().enumerate() {
+        let tx = tx.clone();
+        let f = Arc::clone(&f);
+        pool.execute(move || {
+            let (result, output) = tips::capture(|| f(&item));
+            tx.send((idx, item, result, output)).expect("failed to send job result");
+        });
+    }
+    drop(tx);
+
+    let mut done = BTreeMap::new();
+    let mut results = Vec::with_capacity(total);
+    for (idx, item, result, output) in rx {
+        done.insert(idx, (item, result, output));
+        while let Some((item, result, output)) = done.remove(&results.len()) {
+            output.print();
+            results.push((item, result));
+        }
+    }
+    results
+}
+
+/// Combines the errors of the failed jobs, labelled by name, into one error.
+pub fn check_failures<I>(action: &str, failures: I) -> anyhow::Result<()>
+where
+    I: IntoIterator<Item = (String, anyhow::Error)>,
+{
+    let failures: Vec<_> = failures.into_iter().collect();
+    match failures.as_slice() {
+        [] => Ok(()),
+        [(name, e)] => anyhow::bail!("failed to {action} {name}: {e:#}"),
+        _ => {
+            let summary: Vec<_> =
+                failures.iter().map(|(name, e)| format!("  {name}: {e:#}")).collect();
+            anyhow::bail!("failed to {action} {} bins:\n{}", failures.len(), summary.join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_keeps_order() {
+        let results = run((0..8).collect(), 4, |&n: &u64| {
+            std::thread::sleep(std::time::Duration::from_millis(8 - n));
+            if n % 3 == 0 { anyhow::bail!("{n} failed") } else { Ok(n * 2) }
+        });
+        let items: Vec<_> = results.iter().map(|(n, _)| *n).collect();
+        assert_eq!(items, (0..8).collect::<Vec<_>>());
+        assert_eq!(results[1].1.as_ref().unwrap(), &2);
+        assert_eq!(results[3].1.as_ref().unwrap_err().to_string(), "3 failed");
+    }
+
+    #[test]
+    fn test_check_failures() {
+        assert!(check_failures("build", vec![]).is_ok());
+        let err = check_failures("build", vec![("a".to_string(), anyhow::anyhow!("oops"))]);
+        assert_eq!(err.unwrap_err().to_string(), "failed to build a: oops");
+        let failures =
+            vec![("a".to_string(), anyhow::anyhow!("oops")), ("b".into(), anyhow::anyhow!("no"))];
+        let err = check_failures("build", failures).unwrap_err();
+        assert_eq!(err.to_string(), "failed to build 2 bins:\n  a: oops\n  b: no");
+    }
+}
@@ -1,10 +1,20 @@
+pub mod archive;
 pub mod argc;
+pub mod diff;
 pub mod formatter;
 pub mod fs;
 pub mod hashlib;
+pub mod include;
+pub mod jobs;
+pub mod package;
 pub mod path;
 pub mod process;
 pub mod shell;
+pub mod shfmt;
+pub mod sign;
+pub mod sourcemap;
+pub mod template;
 pub mod terminal;
 pub mod tips;
 pub mod vcs;
+pub mod version;
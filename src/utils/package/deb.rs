@@ -0,0 +1,178 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::{PackageFile, PackageInfo, gzip, parent_dirs};
+use crate::utils::fs::create_file;
+
+/// The version of the `.deb` format.
+const DEB_FORMAT: &[u8] = b"2.0\n";
+
+/// Returns the filename of the package, e.g. `name_1.0.0-1_all.deb`.
+pub fn deb_filename(info: &PackageInfo) -> String {
+    format!("{}_{}-{}_all.deb", info.name, info.version, info.release)
+}
+
+/// Writes a Debian binary package of `files` at `output`.
+///
+/// The package is an `ar` archive of `debian-binary`, `control.tar.gz` and
+/// `data.tar.gz`, the same layout `dpkg-deb --build` creates, so neither
+/// `dpkg-deb` nor a Debian system is needed.
+pub fn write_deb<P: AsRef<Path>>(
+    output: P,
+    info: &PackageInfo,
+    files: &[PackageFile],
+) -> anyhow::Result<()> {
+    let control = PackageFile {
+        path: "/control".into(),
+        content: control_file(info, files).into_bytes(),
+        mode: 0o644,
+    };
+    let control = gzip(&tar(&[control], false, info.mtime)?)?;
+    let data = gzip(&tar(files, true, info.mtime)?)?;
+
+    let mut ar = ar::Builder::new(create_file(output)?);
+    for (name, content) in
+        [("debian-binary", DEB_FORMAT), ("control.tar.gz", &control), ("data.tar.gz", &data)]
+    {
+        let mut header = ar::Header::new(name.as_bytes().to_vec(), content.len() as u64);
+        header.set_mtime(info.mtime);
+        header.set_mode(0o100644);
+        ar.append(&header, content)?;
+    }
+    ar.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// Returns the content of the `control` file.
+fn control_file(info: &PackageInfo, files: &[PackageFile]) -> String {
+    let size: usize = files.iter().map(|x| x.content.len().div_ceil(1024)).sum();
+    let mut control = format!(
+        "Package: {}\nVersion: {}-{}\nArchitecture: all\nMaintainer: {}\nInstalled-Size: {size}\n",
+        info.name, info.version, info.release, info.maintainer
+    );
+    if !info.depends.is_empty() {
+        control.push_str(&format!("Depends: {}\n", info.depends.join(", ")));
+    }
+    control.push_str("Section: utils\nPriority: optional\n");
+    if let Some(homepage) = &info.homepage {
+        control.push_str(&format!("Homepage: {homepage}\n"));
+    }
+    control.push_str(&format!("Description: {}\n", info.summary));
+    control
+}
+
+/// Returns a tar archive of `files` with paths starting with `./`, like the
+/// ones of `dpkg-deb`, with their parent directories if `dirs` is set.
+fn tar(files: &[PackageFile], dirs: bool, mtime: u64) -> io::Result<Vec<u8>> {
+    let mut tar = tar::Builder::new(vec![]);
+    if dirs {
+        append(&mut tar, "./", tar::EntryType::Directory, 0o755, &[], mtime)?;
+        for dir in parent_dirs(files) {
+            let name = format!("./{}/", dir.display());
+            append(&mut tar, &name, tar::EntryType::Directory, 0o755, &[], mtime)?;
+        }
+    }
+    let mut files: Vec<_> = files.iter().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file in files {
+        let name = format!("./{}", file.relative_path().display());
+        append(&mut tar, &name, tar::EntryType::Regular, file.mode, &file.content, mtime)?;
+    }
+    tar.into_inner()
+}
+
+fn append(
+    tar: &mut tar::Builder<Vec<u8>>,
+    name: &str,
+    entry_type: tar::EntryType,
+    mode: u32,
+    content: &[u8],
+    mtime: u64,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_size(content.len() as u64);
+    header.set_mode(mode);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_username("root")?;
+    header.set_groupname("root")?;
+    header.set_mtime(mtime);
+    // `set_path` drops the leading `./`, so short names are written as is.
+    let field = &mut header.as_old_mut().name;
+    if name.len() < field.len() {
+        field[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_cksum();
+        tar.append(&header, content)
+    } else {
+        tar.append_data(&mut header, name, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Read;
+
+    use super::*;
+
+    fn info() -> PackageInfo {
+        PackageInfo {
+            name: "hello".to_string(),
+            version: "1.0.0~rc.1".to_string(),
+            release: "1".to_string(),
+            summary: "Say hello".to_string(),
+            license: Some("MIT".to_string()),
+            homepage: Some("https://example.com".to_string()),
+            maintainer: "Jane <jane@example.com>".to_string(),
+            depends: vec!["bash".to_string(), "curl".to_string()],
+            mtime: 0,
+        }
+    }
+
+    #[test]
+    fn test_write_deb() {
+        let output = std::env::temp_dir().join("test_write_deb").join(deb_filename(&info()));
+        let files = [PackageFile {
+            path: "/usr/bin/hello".into(),
+            content: b"#!/usr/bin/env bash\n".to_vec(),
+            mode: 0o755,
+        }];
+        write_deb(&output, &info(), &files).unwrap();
+        assert!(output.ends_with("hello_1.0.0~rc.1-1_all.deb"));
+
+        let mut archive = ar::Archive::new(fs::File::open(&output).unwrap());
+        let mut names = vec![];
+        while let Some(entry) = archive.next_entry() {
+            let mut entry = entry.unwrap();
+            let name = String::from_utf8(entry.header().identifier().to_vec()).unwrap();
+            let mut content = vec![];
+            entry.read_to_end(&mut content).unwrap();
+            if name == "control.tar.gz" {
+                let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(&content[..]));
+                let mut control = String::new();
+                tar.entries()
+                    .unwrap()
+                    .next()
+                    .unwrap()
+                    .unwrap()
+                    .read_to_string(&mut control)
+                    .unwrap();
+                assert!(control.contains("Version: 1.0.0~rc.1-1\n"));
+                assert!(control.contains("Depends: bash, curl\n"));
+            }
+            if name == "data.tar.gz" {
+                let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(&content[..]));
+                let paths: Vec<_> = tar
+                    .entries()
+                    .unwrap()
+                    .map(|x| String::from_utf8(x.unwrap().path_bytes().to_vec()).unwrap())
+                    .collect();
+                assert_eq!(paths, ["./", "./usr/", "./usr/bin/", "./usr/bin/hello"]);
+            }
+            names.push(name);
+        }
+        assert_eq!(names, ["debian-binary", "control.tar.gz", "data.tar.gz"]);
+        fs::remove_file(output).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+pub mod deb;
+pub mod rpm;
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+/// The metadata of a package.
+#[derive(Debug, Clone)]
+pub struct PackageInfo {
+    pub name: String,
+    /// The upstream version, `-` is replaced with `~` so that pre-releases
+    /// sort before the release.
+    pub version: String,
+    /// The revision of the package.
+    pub release: String,
+    pub summary: String,
+    pub license: Option<String>,
+    pub homepage: Option<String>,
+    pub maintainer: String,
+    pub depends: Vec<String>,
+    /// The mtime of the files and the build time, in seconds since the Unix
+    /// epoch.
+    pub mtime: u64,
+}
+
+/// A file installed by a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageFile {
+    /// The absolute path where the file is installed, e.g. `/usr/bin/name`.
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+    /// The permission bits, e.g. `0o755`.
+    pub mode: u32,
+}
+
+impl PackageFile {
+    /// Returns the path without the leading `/`.
+    fn relative_path(&self) -> &Path {
+        self.path.strip_prefix("/").unwrap_or(&self.path)
+    }
+}
+
+/// Converts a semver version to the package version syntax, where `~` sorts
+/// before anything, e.g. `1.0.0-rc.1` becomes `1.0.0~rc.1`.
+pub fn package_version(version: &str) -> String {
+    version.replace('-', "~")
+}
+
+/// Compresses `content` with gzip, without a timestamp in the header.
+pub fn gzip(content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::best());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+/// Returns the parent directories of `files`, without `/`, parents first.
+fn parent_dirs(files: &[PackageFile]) -> BTreeSet<PathBuf> {
+    files
+        .iter()
+        .flat_map(|file| file.relative_path().ancestors().skip(1))
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .collect()
+}
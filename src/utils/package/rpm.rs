@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use super::{PackageFile, PackageInfo, gzip};
+use crate::utils::fs::create_file;
+
+const LEAD_MAGIC: [u8; 4] = [0xed, 0xab, 0xee, 0xdb];
+const HEADER_MAGIC: [u8; 8] = [0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0];
+/// The signature type of the lead, a header follows it.
+const SIGTYPE_HEADERSIG: u16 = 5;
+
+// Tags of the signature header.
+const SIGTAG_HEADERSIGNATURES: u32 = 62;
+const SIGTAG_SHA256: u32 = 273;
+const SIGTAG_SIZE: u32 = 1000;
+const SIGTAG_PAYLOADSIZE: u32 = 1007;
+
+// Tags of the main header.
+const TAG_HEADERIMMUTABLE: u32 = 63;
+const TAG_HEADERI18NTABLE: u32 = 100;
+const TAG_NAME: u32 = 1000;
+const TAG_VERSION: u32 = 1001;
+const TAG_RELEASE: u32 = 1002;
+const TAG_SUMMARY: u32 = 1004;
+const TAG_DESCRIPTION: u32 = 1005;
+const TAG_BUILDTIME: u32 = 1006;
+const TAG_SIZE: u32 = 1009;
+const TAG_LICENSE: u32 = 1014;
+const TAG_PACKAGER: u32 = 1015;
+const TAG_GROUP: u32 = 1016;
+const TAG_URL: u32 = 1020;
+const TAG_OS: u32 = 1021;
+const TAG_ARCH: u32 = 1022;
+const TAG_FILESIZES: u32 = 1028;
+const TAG_FILEMODES: u32 = 1030;
+const TAG_FILERDEVS: u32 = 1033;
+const TAG_FILEMTIMES: u32 = 1034;
+const TAG_FILEDIGESTS: u32 = 1035;
+const TAG_FILELINKTOS: u32 = 1036;
+const TAG_FILEFLAGS: u32 = 1037;
+const TAG_FILEUSERNAME: u32 = 1039;
+const TAG_FILEGROUPNAME: u32 = 1040;
+const TAG_SOURCERPM: u32 = 1044;
+const TAG_FILEVERIFYFLAGS: u32 = 1045;
+const TAG_PROVIDENAME: u32 = 1047;
+const TAG_REQUIREFLAGS: u32 = 1048;
+const TAG_REQUIRENAME: u32 = 1049;
+const TAG_REQUIREVERSION: u32 = 1050;
+const TAG_FILEDEVICES: u32 = 1095;
+const TAG_FILEINODES: u32 = 1096;
+const TAG_FILELANGS: u32 = 1097;
+const TAG_PROVIDEFLAGS: u32 = 1112;
+const TAG_PROVIDEVERSION: u32 = 1113;
+const TAG_DIRINDEXES: u32 = 1116;
+const TAG_BASENAMES: u32 = 1117;
+const TAG_DIRNAMES: u32 = 1118;
+const TAG_PAYLOADFORMAT: u32 = 1124;
+const TAG_PAYLOADCOMPRESSOR: u32 = 1125;
+const TAG_PAYLOADFLAGS: u32 = 1126;
+const TAG_FILEDIGESTALGO: u32 = 5011;
+const TAG_PAYLOADDIGEST: u32 = 5092;
+const TAG_PAYLOADDIGESTALGO: u32 = 5093;
+
+/// The digest algorithm of files and payload, SHA-256.
+const DIGESTALGO_SHA256: u32 = 8;
+const SENSE_EQUAL: u32 = 1 << 3;
+const SENSE_LESS: u32 = 1 << 1;
+const SENSE_RPMLIB: u32 = 1 << 24;
+/// The features of rpm the package relies on.
+const RPMLIB_FEATURES: [(&str, &str); 3] = [
+    ("rpmlib(CompressedFileNames)", "3.0.4-1"),
+    ("rpmlib(FileDigests)", "4.6.0-1"),
+    ("rpmlib(PayloadFilesHavePrefix)", "4.0-1"),
+];
+/// The feature of rpm 4.10 needed to compare the `~` of pre-releases, e.g.
+/// `1.0.0~rc.1`.
+const RPMLIB_TILDE: &str = "rpmlib(TildeInVersions)";
+
+/// Returns the filename of the package, e.g. `name-1.0.0-1.noarch.rpm`.
+pub fn rpm_filename(info: &PackageInfo) -> String {
+    format!("{}-{}-{}.noarch.rpm", info.name, info.version, info.release)
+}
+
+/// Writes an RPM package of `files` at `output`.
+///
+/// The package is the lead, a signature header with the digests of the
+/// package, the main header and a gzipped `cpio` payload, the layout
+/// `rpmbuild` creates, so neither `rpmbuild` nor an RPM system is needed.
+/// `info.depends` are capabilities, e.g. `/usr/bin/curl`.
+pub fn write_rpm<P: AsRef<Path>>(
+    output: P,
+    info: &PackageInfo,
+    files: &[PackageFile],
+) -> anyhow::Result<()> {
+    let mut files: Vec<_> = files.iter().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let cpio = cpio(&files, info.mtime);
+    let payload = gzip(&cpio)?;
+    let header = main_header(info, &files, &payload)?.to_bytes(TAG_HEADERIMMUTABLE);
+
+    let mut signature = Header::default();
+    signature.add(SIGTAG_SHA256, Value::String(hex::encode(Sha256::digest(&header))));
+    signature.add(SIGTAG_SIZE, Value::Int32(vec![u32::try_from(header.len() + payload.len())?]));
+    signature.add(SIGTAG_PAYLOADSIZE, Value::Int32(vec![u32::try_from(cpio.len())?]));
+    let mut signature = signature.to_bytes(SIGTAG_HEADERSIGNATURES);
+    signature.resize(signature.len().next_multiple_of(8), 0);
+
+    let mut fp = create_file(output)?;
+    fp.write_all(&lead(info))?;
+    fp.write_all(&signature)?;
+    fp.write_all(&header)?;
+    fp.write_all(&payload)?;
+    fp.flush()?;
+    Ok(())
+}
+
+/// Returns the 96-byte lead, only kept for compatibility with old tools.
+fn lead(info: &PackageInfo) -> Vec<u8> {
+    let mut lead = LEAD_MAGIC.to_vec();
+    lead.extend([3, 0]); // format version
+    lead.extend(0u16.to_be_bytes()); // binary package
+    lead.extend(0u16.to_be_bytes()); // architecture
+    let mut name = format!("{}-{}-{}", info.name, info.version, info.release).into_bytes();
+    name.resize(66, 0);
+    name[65] = 0;
+    lead.extend(name);
+    lead.extend(1u16.to_be_bytes()); // Linux
+    lead.extend(SIGTYPE_HEADERSIG.to_be_bytes());
+    lead.resize(96, 0);
+    lead
+}
+
+fn main_header(
+    info: &PackageInfo,
+    files: &[&PackageFile],
+    payload: &[u8],
+) -> anyhow::Result<Header> {
+    let count = files.len();
+    let mut dirs: BTreeMap<String, u32> = BTreeMap::new();
+    let mut dir_indexes = vec![];
+    let mut basenames = vec![];
+    for file in files {
+        let dir = file.path.parent().unwrap_or(Path::new("/")).display().to_string();
+        let dir = if dir.ends_with('/') { dir } else { format!("{dir}/") };
+        let next = dirs.len() as u32;
+        dir_indexes.push(*dirs.entry(dir).or_insert(next));
+        basenames.push(file.path.file_name().unwrap_or_default().to_string_lossy().to_string());
+    }
+    let mut dirnames: Vec<_> = dirs.into_iter().collect();
+    dirnames.sort_by_key(|(_, index)| *index);
+    let size: usize = files.iter().map(|x| x.content.len()).sum();
+
+    let mut requires: Vec<_> = info.depends.iter().map(|x| (x.as_str(), 0, "")).collect();
+    requires.extend(
+        RPMLIB_FEATURES
+            .iter()
+            .map(|(name, version)| (*name, SENSE_RPMLIB | SENSE_LESS | SENSE_EQUAL, *version)),
+    );
+    if info.version.contains('~') || info.release.contains('~') {
+        requires.push((RPMLIB_TILDE, SENSE_RPMLIB | SENSE_LESS | SENSE_EQUAL, "4.10.0-1"));
+    }
+    let version_release = format!("{}-{}", info.version, info.release);
+    let strings = |x: &str| Value::StringArray(vec![x.to_string(); count]);
+
+    let mut header = Header::default();
+    header.add(TAG_HEADERI18NTABLE, Value::StringArray(vec!["C".to_string()]));
+    header.add(TAG_NAME, Value::String(info.name.clone()));
+    header.add(TAG_VERSION, Value::String(info.version.clone()));
+    header.add(TAG_RELEASE, Value::String(info.release.clone()));
+    header.add(TAG_SUMMARY, Value::I18nString(info.summary.clone()));
+    header.add(TAG_DESCRIPTION, Value::I18nString(info.summary.clone()));
+    header.add(TAG_BUILDTIME, Value::Int32(vec![u32::try_from(info.mtime)?]));
+    header.add(TAG_SIZE, Value::Int32(vec![u32::try_from(size)?]));
+    let license = info.license.as_deref().unwrap_or("Unspecified");
+    header.add(TAG_LICENSE, Value::String(license.to_string()));
+    header.add(TAG_PACKAGER, Value::String(info.maintainer.clone()));
+    header.add(TAG_GROUP, Value::I18nString("Unspecified".to_string()));
+    if let Some(homepage) = &info.homepage {
+        header.add(TAG_URL, Value::String(homepage.clone()));
+    }
+    header.add(TAG_OS, Value::String("linux".to_string()));
+    header.add(TAG_ARCH, Value::String("noarch".to_string()));
+    header.add(TAG_FILESIZES, Value::Int32(files.iter().map(|x| x.content.len() as u32).collect()));
+    header.add(
+        TAG_FILEMODES,
+        Value::Int16(files.iter().map(|x| (0o100000 | x.mode) as u16).collect()),
+    );
+    header.add(TAG_FILERDEVS, Value::Int16(vec![0; count]));
+    header.add(TAG_FILEMTIMES, Value::Int32(vec![info.mtime as u32; count]));
+    header.add(
+        TAG_FILEDIGESTS,
+        Value::StringArray(files.iter().map(|x| hex::encode(Sha256::digest(&x.content))).collect()),
+    );
+    header.add(TAG_FILELINKTOS, strings(""));
+    header.add(TAG_FILEFLAGS, Value::Int32(vec![0; count]));
+    header.add(TAG_FILEUSERNAME, strings("root"));
+    header.add(TAG_FILEGROUPNAME, strings("root"));
+    let source = format!("{}-{version_release}.src.rpm", info.name);
+    header.add(TAG_SOURCERPM, Value::String(source));
+    header.add(TAG_FILEVERIFYFLAGS, Value::Int32(vec![u32::MAX; count]));
+    header.add(TAG_PROVIDENAME, Value::StringArray(vec![info.name.clone()]));
+    header.add(TAG_PROVIDEFLAGS, Value::Int32(vec![SENSE_EQUAL]));
+    header.add(TAG_PROVIDEVERSION, Value::StringArray(vec![version_release]));
+    header.add(
+        TAG_REQUIRENAME,
+        Value::StringArray(requires.iter().map(|x| x.0.to_string()).collect()),
+    );
+    header.add(TAG_REQUIREFLAGS, Value::Int32(requires.iter().map(|x| x.1).collect()));
+    header.add(
+        TAG_REQUIREVERSION,
+        Value::StringArray(requires.iter().map(|x| x.2.to_string()).collect()),
+    );
+    header.add(TAG_FILEDEVICES, Value::Int32(vec![1; count]));
+    header.add(TAG_FILEINODES, Value::Int32((1..=count as u32).collect()));
+    header.add(TAG_FILELANGS, strings(""));
+    header.add(TAG_DIRINDEXES, Value::Int32(dir_indexes));
+    header.add(TAG_BASENAMES, Value::StringArray(basenames));
+    header.add(TAG_DIRNAMES, Value::StringArray(dirnames.into_iter().map(|x| x.0).collect()));
+    header.add(TAG_PAYLOADFORMAT, Value::String("cpio".to_string()));
+    header.add(TAG_PAYLOADCOMPRESSOR, Value::String("gzip".to_string()));
+    header.add(TAG_PAYLOADFLAGS, Value::String("9".to_string()));
+    header.add(TAG_FILEDIGESTALGO, Value::Int32(vec![DIGESTALGO_SHA256]));
+    header.add(TAG_PAYLOADDIGEST, Value::StringArray(vec![hex::encode(Sha256::digest(payload))]));
+    header.add(TAG_PAYLOADDIGESTALGO, Value::Int32(vec![DIGESTALGO_SHA256]));
+    Ok(header)
+}
+
+/// Returns a `newc` cpio archive of `files`, with the inode numbers of the
+/// header.
+fn cpio(files: &[&PackageFile], mtime: u64) -> Vec<u8> {
+    let mut cpio = vec![];
+    let mut append = |ino: usize, mode: u32, name: &str, content: &[u8]| {
+        let fields = [ino, mode as usize, 0, 0, 1, mtime as usize, content.len(), 0, 0, 0, 0];
+        cpio.extend(b"070701");
+        for field in fields {
+            cpio.extend(format!("{field:08x}").as_bytes());
+        }
+        cpio.extend(format!("{:08x}", name.len() + 1).as_bytes());
+        cpio.extend(b"00000000");
+        cpio.extend(name.as_bytes());
+        cpio.push(0);
+        cpio.resize(cpio.len().next_multiple_of(4), 0);
+        cpio.extend(content);
+        cpio.resize(cpio.len().next_multiple_of(4), 0);
+    };
+    for (i, file) in files.iter().enumerate() {
+        let name = format!(".{}", file.path.display());
+        append(i + 1, 0o100000 | file.mode, &name, &file.content);
+    }
+    append(0, 0, "TRAILER!!!", &[]);
+    cpio
+}
+
+/// The value of a header entry.
+enum Value {
+    Int16(Vec<u16>),
+    Int32(Vec<u32>),
+    String(String),
+    StringArray(Vec<String>),
+    I18nString(String),
+}
+
+impl Value {
+    /// Returns the type, alignment and count of the value.
+    fn info(&self) -> (u32, usize, usize) {
+        match self {
+            Self::Int16(x) => (3, 2, x.len()),
+            Self::Int32(x) => (4, 4, x.len()),
+            Self::String(_) => (6, 1, 1),
+            Self::StringArray(x) => (8, 1, x.len()),
+            Self::I18nString(_) => (9, 1, 1),
+        }
+    }
+
+    fn write(&self, data: &mut Vec<u8>) {
+        match self {
+            Self::Int16(x) => x.iter().for_each(|x| data.extend(x.to_be_bytes())),
+            Self::Int32(x) => x.iter().for_each(|x| data.extend(x.to_be_bytes())),
+            Self::String(x) | Self::I18nString(x) => {
+                data.extend(x.as_bytes());
+                data.push(0);
+            }
+            Self::StringArray(x) => x.iter().for_each(|x| {
+                data.extend(x.as_bytes());
+                data.push(0);
+            }),
+        }
+    }
+}
+
+/// A header structure, used for both the signature and the main header.
+#[derive(Default)]
+struct Header {
+    entries: BTreeMap<u32, Value>,
+}
+
+impl Header {
+    fn add(&mut self, tag: u32, value: Value) {
+        self.entries.insert(tag, value);
+    }
+
+    /// Encodes the header, with all entries in an immutable region `region`,
+    /// as rpm expects of signed headers.
+    fn to_bytes(&self, region: u32) -> Vec<u8> {
+        let count = self.entries.len() + 1;
+        let mut index = vec![];
+        let mut data = vec![];
+        for (tag, value) in &self.entries {
+            let (kind, align, n) = value.info();
+            data.resize(data.len().next_multiple_of(align), 0);
+            index.push((*tag, kind, data.len(), n));
+            value.write(&mut data);
+        }
+        // The trailer of the region is an index entry pointing back to the
+        // start of the index.
+        let trailer = data.len();
+        data.extend(region.to_be_bytes());
+        data.extend(7u32.to_be_bytes());
+        data.extend((-(count as i32 * 16)).to_be_bytes());
+        data.extend(16u32.to_be_bytes());
+        index.insert(0, (region, 7, trailer, 16));
+
+        let mut bytes = HEADER_MAGIC.to_vec();
+        bytes.extend((count as u32).to_be_bytes());
+        bytes.extend((data.len() as u32).to_be_bytes());
+        for (tag, kind, offset, n) in index {
+            for field in [tag, kind, offset as u32, n as u32] {
+                bytes.extend(field.to_be_bytes());
+            }
+        }
+        bytes.extend(data);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Read;
+
+    use super::*;
+
+    /// Returns the tags of the header at `offset`, and the offset of its end.
+    fn read_header(bytes: &[u8], offset: usize) -> (Vec<u32>, usize) {
+        assert_eq!(bytes[offset..offset + 8], HEADER_MAGIC);
+        let be = |i: usize| u32::from_be_bytes(bytes[i..i + 4].try_into().unwrap()) as usize;
+        let (count, size) = (be(offset + 8), be(offset + 12));
+        let tags = (0..count).map(|i| be(offset + 16 + i * 16) as u32).collect();
+        (tags, offset + 16 + count * 16 + size)
+    }
+
+    fn package_info(version: &str) -> PackageInfo {
+        PackageInfo {
+            name: "hello".to_string(),
+            version: version.to_string(),
+            release: "1".to_string(),
+            summary: "Say hello".to_string(),
+            license: None,
+            homepage: None,
+            maintainer: "Jane <jane@example.com>".to_string(),
+            depends: vec!["/usr/bin/curl".to_string()],
+            mtime: 0,
+        }
+    }
+
+    fn requires(info: &PackageInfo) -> Vec<String> {
+        let header = main_header(info, &[], &[]).unwrap();
+        match &header.entries[&TAG_REQUIRENAME] {
+            Value::StringArray(names) => names.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_write_rpm() {
+        let info = package_info("1.0.0");
+        let output = std::env::temp_dir().join("test_write_rpm").join(rpm_filename(&info));
+        let files = [PackageFile {
+            path: "/usr/bin/hello".into(),
+            content: b"#!/usr/bin/env bash\n".to_vec(),
+            mode: 0o755,
+        }];
+        write_rpm(&output, &info, &files).unwrap();
+        assert!(output.ends_with("hello-1.0.0-1.noarch.rpm"));
+
+        let bytes = fs::read(&output).unwrap();
+        assert_eq!(bytes[..4], LEAD_MAGIC);
+        let (tags, end) = read_header(&bytes, 96);
+        assert_eq!(tags, [SIGTAG_HEADERSIGNATURES, SIGTAG_SHA256, SIGTAG_SIZE, SIGTAG_PAYLOADSIZE]);
+        let (tags, end) = read_header(&bytes, end.next_multiple_of(8));
+        assert_eq!(tags[..3], [TAG_HEADERIMMUTABLE, TAG_HEADERI18NTABLE, TAG_NAME]);
+
+        let mut cpio = vec![];
+        flate2::read::GzDecoder::new(&bytes[end..]).read_to_end(&mut cpio).unwrap();
+        assert!(cpio.starts_with(b"070701"));
+        assert!(cpio.windows(16).any(|x| x == b"./usr/bin/hello\0"));
+        fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn test_requires() {
+        let names = requires(&package_info("1.0.0"));
+        assert_eq!(names[0], "/usr/bin/curl");
+        assert!(!names.iter().any(|x| x == RPMLIB_TILDE));
+        assert!(requires(&package_info("1.0.0~rc.1")).iter().any(|x| x == RPMLIB_TILDE));
+    }
+}
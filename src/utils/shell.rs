@@ -1,12 +1,52 @@
+use std::fs;
 use std::path::Path;
 use std::process::{Command, Stdio};
 
+use anyhow::Context;
 use which::which;
 
+use super::{shfmt, tips};
+use crate::config::ShellFormatter;
 use crate::prelude::*;
 
+/// Formats the script at `p` with the formatter selected by
+/// `tools.formatter`.
 #[allow(dead_code)]
 pub fn fmt_shell<P: AsRef<Path>>(p: P) -> anyhow::Result<()> {
+    match CONFIG.formatter() {
+        Some(ShellFormatter::Shfmt) => run_shfmt(p),
+        Some(ShellFormatter::Builtin) => run_builtin_fmt(p),
+        Some(ShellFormatter::None) => Ok(()),
+        None if which("shfmt").is_ok() => run_shfmt(p),
+        None => {
+            log::debug!("shfmt is not installed, using the built-in formatter");
+            run_builtin_fmt(p)
+        }
+    }
+}
+
+/// Returns the formatter used by [`fmt_shell`] and its version, e.g.
+/// `shfmt v3.8.0`.
+pub fn formatter_version() -> String {
+    match CONFIG.formatter() {
+        Some(ShellFormatter::Shfmt) => shfmt_version(),
+        Some(ShellFormatter::Builtin) => "builtin".to_string(),
+        Some(ShellFormatter::None) => "none".to_string(),
+        None if which("shfmt").is_ok() => shfmt_version(),
+        None => "builtin".to_string(),
+    }
+}
+
+fn shfmt_version() -> String {
+    let version = which("shfmt")
+        .ok()
+        .and_then(|cmd| Command::new(cmd).arg("--version").output().ok())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+    format!("shfmt {version}")
+}
+
+fn run_shfmt<P: AsRef<Path>>(p: P) -> anyhow::Result<()> {
     if let Ok(cmd) = which("shfmt") {
         let output = Command::new(cmd)
             .args(CONFIG.shfmt_options())
@@ -33,3 +73,54 @@ pub fn fmt_shell<P: AsRef<Path>>(p: P) -> anyhow::Result<()> {
         anyhow::bail!("shfmt is not installed or not in PATH.")
     }
 }
+
+fn run_builtin_fmt<P: AsRef<Path>>(p: P) -> anyhow::Result<()> {
+    let path = p.as_ref();
+    let (options, ignored) = shfmt::Options::parse(&CONFIG.shfmt_options())?;
+    for option in ignored {
+        tips::warning(&format!("the built-in formatter ignores the shfmt option '{option}'"));
+    }
+    let source = fs::read_to_string(path)?;
+    fs::write(path, shfmt::format(&source, &options))
+        .with_context(|| format!("failed to write '{}'", path.display()))
+}
+
+/// A finding reported by shellcheck.
+#[derive(serde::Deserialize, Debug)]
+pub struct ShellcheckComment {
+    pub line: usize,
+    pub level: String,
+    pub code: u32,
+    pub message: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct ShellcheckOutput {
+    comments: Vec<ShellcheckComment>,
+}
+
+/// Runs shellcheck on `p` with the `[tools.shellcheck]` settings and returns
+/// its findings.
+#[allow(dead_code)]
+pub fn shellcheck<P: AsRef<Path>>(p: P) -> anyhow::Result<Vec<ShellcheckComment>> {
+    let Ok(cmd) = which("shellcheck") else {
+        anyhow::bail!("shellcheck is not installed or not in PATH.")
+    };
+    let settings = CONFIG.shellcheck();
+    let mut command = Command::new(cmd);
+    command.arg("--format=json1").arg(format!("--severity={}", settings.severity().as_str()));
+    if !settings.exclude().is_empty() {
+        command.arg(format!("--exclude={}", settings.exclude().join(",")));
+    }
+    let output = command
+        .arg(p.as_ref())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|err| anyhow::format_err!("failed to execute shellcheck: {err}"))?;
+    // Exit status 1 only means that findings were reported.
+    if !matches!(output.status.code(), Some(0 | 1)) {
+        anyhow::bail!("shellcheck failed with error:\n{}", String::from_utf8_lossy(&output.stderr))
+    }
+    let output: ShellcheckOutput = serde_json::from_slice(&output.stdout)?;
+    Ok(output.comments)
+}
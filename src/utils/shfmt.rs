@@ -0,0 +1,652 @@
+//! A built-in fallback for `shfmt`.
+//!
+//! Only the layout is normalized: lines are re-indented, trailing whitespace
+//! and repeated blank lines are removed and, with `-bn`, binary operators are
+//! moved to the start of the next line. Heredocs and multi-line strings are
+//! kept verbatim.
+
+use std::collections::VecDeque;
+
+/// Languages accepted by `-ln`, the built-in formatter treats them alike.
+const LANGUAGES: [&str; 5] = ["auto", "bash", "posix", "mksh", "bats"];
+
+/// The subset of `shfmt` options supported by the built-in formatter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Spaces per indentation level, `0` for tabs.
+    indent: usize,
+    /// Indent the items of `case` statements (`-ci`).
+    case_indent: bool,
+    /// Start lines with binary operators instead of ending them (`-bn`).
+    binary_next_line: bool,
+}
+
+impl Options {
+    /// Parses `shfmt` command line options, returning the options that are
+    /// not supported and have been ignored.
+    pub fn parse(args: &[&str]) -> anyhow::Result<(Self, Vec<String>)> {
+        let mut options = Self::default();
+        let mut ignored = vec![];
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .or_else(|| args.next().copied())
+                    .ok_or_else(|| anyhow::format_err!("missing value for shfmt option '{name}'"))
+            };
+            match name {
+                "-i" | "--indent" => {
+                    let value = value()?;
+                    options.indent = value.parse().map_err(|_| {
+                        anyhow::format_err!("invalid value for shfmt option '{name}': {value}")
+                    })?;
+                }
+                "-ci" | "--case-indent" => options.case_indent = true,
+                "-bn" | "--binary-next-line" => options.binary_next_line = true,
+                "-ln" | "--language-dialect" => {
+                    let value = value()?;
+                    if !LANGUAGES.contains(&value) {
+                        anyhow::bail!("unsupported shfmt language '{value}'");
+                    }
+                }
+                // The built-in formatter always writes the file in place.
+                "-w" | "--write" => {}
+                _ => ignored.push(arg.to_string()),
+            }
+        }
+        Ok((options, ignored))
+    }
+}
+
+/// Formats a shell script.
+pub fn format(source: &str, options: &Options) -> String {
+    let mut formatter = Formatter::new(options);
+    let lines: Vec<_> = source.lines().collect();
+    for (idx, line) in lines.iter().enumerate() {
+        formatter.line(line, lines.get(idx + 1).copied());
+    }
+    formatter.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    /// `{ ... }`
+    Brace,
+    /// `( ... )`, `$( ... )` or `=( ... )`
+    Paren,
+    /// `then ... fi` or `do ... done`
+    Keyword,
+    /// `case ... in`, before the `in` keyword.
+    CaseHead,
+    /// The items of a `case` statement.
+    Case,
+    /// The commands of a `case` item, up to `;;`.
+    CaseItem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    Single,
+    AnsiC,
+    Double,
+    Backtick,
+}
+
+#[derive(Debug)]
+struct Heredoc {
+    delimiter: String,
+    strip_tabs: bool,
+}
+
+/// What the scan of a line found out.
+#[derive(Debug, Default)]
+struct Scan {
+    /// Indentation level of the line.
+    level: usize,
+    /// Byte offset of the comment of the line, if any.
+    comment: Option<usize>,
+    /// The binary operator ending the line, if any.
+    trailing_op: Option<&'static str>,
+    /// Whether the line ends with a backslash.
+    escaped_newline: bool,
+}
+
+struct Formatter<'a> {
+    options: &'a Options,
+    output: Vec<String>,
+    stack: Vec<Block>,
+    /// The quote left open by the previous line.
+    quote: Option<Quote>,
+    /// Heredocs started by the previous lines, in order.
+    heredocs: VecDeque<Heredoc>,
+    /// Whether the previous line is continued by this one.
+    continued: bool,
+    /// Whether the previous line ended with a binary operator.
+    after_op: bool,
+    /// Operator moved from the end of the previous line.
+    pending_op: Option<&'static str>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(options: &'a Options) -> Self {
+        Self {
+            options,
+            output: vec![],
+            stack: vec![],
+            quote: None,
+            heredocs: VecDeque::new(),
+            continued: false,
+            after_op: false,
+            pending_op: None,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        while self.output.last().is_some_and(String::is_empty) {
+            self.output.pop();
+        }
+        let mut output = self.output.join("\n");
+        output.push('\n');
+        output
+    }
+
+    fn depth(&self) -> usize {
+        self.stack
+            .iter()
+            .map(|block| match block {
+                Block::CaseHead => 0,
+                Block::Case => usize::from(self.options.case_indent),
+                _ => 1,
+            })
+            .sum()
+    }
+
+    fn indent(&self, level: usize) -> String {
+        match self.options.indent {
+            0 => "\t".repeat(level),
+            n => " ".repeat(n * level),
+        }
+    }
+
+    fn line(&mut self, line: &str, next: Option<&str>) {
+        if let Some(heredoc) = self.heredocs.front() {
+            let body = if heredoc.strip_tabs { line.trim_start_matches('\t') } else { line };
+            if body == heredoc.delimiter {
+                self.heredocs.pop_front();
+            }
+            self.output.push(line.to_string());
+            return;
+        }
+        if self.quote.is_some() {
+            // The line starts inside a string, so it is kept as is.
+            self.output.push(line.to_string());
+            self.scan(line, false);
+            return;
+        }
+
+        // A backslash followed by spaces escapes a space, not the newline.
+        let trimmed = if line.trim_end().ends_with('\\') { line.trim_start() } else { line.trim() };
+        if trimmed.is_empty() {
+            if self.output.last().is_some_and(|x| !x.is_empty()) {
+                self.output.push(String::new());
+            }
+            self.continued = false;
+            return;
+        }
+        let prefix = self.pending_op.take().map(|op| format!("{op} ")).unwrap_or_default();
+        let continued = std::mem::take(&mut self.continued);
+        let command_start = !continued || std::mem::take(&mut self.after_op);
+        let scan = self.scan(&format!("{prefix}{trimmed}"), command_start);
+        let level = scan.level + usize::from(continued);
+
+        // Trailing whitespace of a string continued on the next line is kept.
+        let mut text = match self.quote {
+            Some(_) => format!("{prefix}{}", line.trim_start()),
+            None => format!("{prefix}{trimmed}"),
+        };
+        if let Some(op) = scan.trailing_op {
+            let next_is_code =
+                next.map(str::trim).is_some_and(|x| !x.is_empty() && !x.starts_with('#'));
+            if self.options.binary_next_line
+                && scan.comment.is_none()
+                && self.heredocs.is_empty()
+                && next_is_code
+            {
+                let code = text.trim_end().strip_suffix(op).unwrap_or(&text).trim_end();
+                text = format!("{code} \\");
+                self.pending_op = Some(op);
+            }
+            self.continued = true;
+            self.after_op = true;
+        }
+        if scan.escaped_newline {
+            self.continued = true;
+        }
+        self.output.push(format!("{}{text}", self.indent(level)));
+    }
+
+    /// Closes the current `case` item, but not the one of an outer `case`.
+    fn pop_case_item(&mut self) {
+        if self.stack.last() == Some(&Block::CaseItem) {
+            self.stack.pop();
+        }
+    }
+
+    fn pop(&mut self, block: Block) {
+        if let Some(pos) = self.stack.iter().rposition(|x| *x == block) {
+            self.stack.truncate(pos);
+        }
+    }
+
+    /// Scans a line, updating the nesting of blocks and quotes.
+    fn scan(&mut self, line: &str, command_start: bool) -> Scan {
+        let mut scan = Scan::default();
+        let chars: Vec<(usize, char)> = line.char_indices().collect();
+        let mut level = None;
+        let mut leading = true;
+        let mut command_start = command_start;
+        let mut word = String::new();
+        let mut word_quoted = false;
+        let mut idx = 0;
+
+        if let Some(quote) = self.quote.take() {
+            match close_quote(&chars, 0, quote) {
+                Some(end) => idx = end,
+                None => {
+                    self.quote = Some(quote);
+                    return scan;
+                }
+            }
+            leading = false;
+        }
+
+        // Applies a finished word and decides where the line is indented.
+        macro_rules! end_word {
+            () => {
+                if !word.is_empty() {
+                    let keyword = if word_quoted { "" } else { word.as_str() };
+                    self.word(keyword, &mut command_start, &mut leading, &mut level);
+                    word.clear();
+                    word_quoted = false;
+                    scan.trailing_op = None;
+                }
+            };
+        }
+
+        while idx < chars.len() {
+            let (offset, c) = chars[idx];
+            match c {
+                ' ' | '\t' => end_word!(),
+                '#' if word.is_empty() => {
+                    scan.comment = Some(offset);
+                    break;
+                }
+                '\\' => {
+                    if idx + 1 == chars.len() {
+                        end_word!();
+                        scan.escaped_newline = true;
+                        break;
+                    }
+                    word.push(c);
+                    word.push(chars[idx + 1].1);
+                    word_quoted = true;
+                    idx += 1;
+                }
+                '\'' | '"' | '`' => {
+                    let quote = match c {
+                        '\'' if word.ends_with('$') => Quote::AnsiC,
+                        '\'' => Quote::Single,
+                        '"' => Quote::Double,
+                        _ => Quote::Backtick,
+                    };
+                    word.push(c);
+                    word_quoted = true;
+                    match close_quote(&chars, idx + 1, quote) {
+                        Some(end) => {
+                            let end_offset = chars.get(end).map_or(line.len(), |x| x.0);
+                            word.push_str(&line[chars[idx + 1].0.min(end_offset)..end_offset]);
+                            idx = end;
+                            continue;
+                        }
+                        None => {
+                            self.quote = Some(quote);
+                            break;
+                        }
+                    }
+                }
+                '{' if word.ends_with('$') => {
+                    // Parameter expansion, e.g. `${name:-default}`.
+                    let end = close_brace(&chars, idx + 1);
+                    let end_offset = chars.get(end).map_or(line.len(), |x| x.0);
+                    word.push_str(&line[offset..end_offset]);
+                    word_quoted = true;
+                    idx = end;
+                    continue;
+                }
+                '(' => {
+                    if self.stack.last() == Some(&Block::Case) && word.is_empty() {
+                        // Optional opening parenthesis of a `case` pattern.
+                    } else {
+                        self.set_level(&mut leading, &mut level);
+                        word.clear();
+                        word_quoted = false;
+                        self.stack.push(Block::Paren);
+                        command_start = true;
+                        scan.trailing_op = None;
+                    }
+                }
+                ')' => {
+                    // `()` of a function definition may be followed by `{`.
+                    let empty = idx > 0 && chars[idx - 1].1 == '(';
+                    word.clear();
+                    word_quoted = false;
+                    if self.stack.last() == Some(&Block::Case) {
+                        self.set_level(&mut leading, &mut level);
+                        self.stack.push(Block::CaseItem);
+                        command_start = true;
+                    } else {
+                        if self.stack.last() == Some(&Block::Paren) {
+                            self.stack.pop();
+                        }
+                        command_start = empty;
+                    }
+                    scan.trailing_op = None;
+                }
+                ';' => {
+                    end_word!();
+                    self.set_level(&mut leading, &mut level);
+                    let next = chars.get(idx + 1).map(|x| x.1);
+                    if matches!(next, Some(';' | '&')) {
+                        self.pop_case_item();
+                        idx += 1;
+                        if next == Some(';') && chars.get(idx + 1).map(|x| x.1) == Some('&') {
+                            idx += 1;
+                        }
+                    }
+                    command_start = true;
+                    scan.trailing_op = None;
+                }
+                '&' | '|' => {
+                    end_word!();
+                    let next = chars.get(idx + 1).map(|x| x.1);
+                    if self.stack.last() == Some(&Block::Case) {
+                        // Separator of `case` patterns.
+                        idx += 1;
+                        continue;
+                    }
+                    self.set_level(&mut leading, &mut level);
+                    scan.trailing_op = match (c, next) {
+                        ('&', Some('&')) => Some("&&"),
+                        ('|', Some('|')) => Some("||"),
+                        ('|', Some('&')) => None,
+                        ('|', _) => Some("|"),
+                        ('&', Some('>')) => {
+                            // Redirection of both stdout and stderr.
+                            word.push_str("&>");
+                            idx += 2;
+                            continue;
+                        }
+                        _ => None,
+                    };
+                    if next == Some(c) || (c == '|' && next == Some('&')) {
+                        idx += 1;
+                    }
+                    command_start = true;
+                    idx += 1;
+                    continue;
+                }
+                '<' if chars.get(idx + 1).map(|x| x.1) == Some('<') => {
+                    end_word!();
+                    self.set_level(&mut leading, &mut level);
+                    if chars.get(idx + 2).map(|x| x.1) == Some('<') {
+                        // Here string.
+                        idx += 3;
+                        continue;
+                    }
+                    idx += 2;
+                    let strip_tabs = chars.get(idx).map(|x| x.1) == Some('-');
+                    if strip_tabs {
+                        idx += 1;
+                    }
+                    while chars.get(idx).is_some_and(|x| x.1 == ' ' || x.1 == '\t') {
+                        idx += 1;
+                    }
+                    let mut delimiter = String::new();
+                    while let Some(&(_, c)) = chars.get(idx) {
+                        if c.is_whitespace() || matches!(c, ';' | '&' | '|' | '<' | '>' | ')') {
+                            break;
+                        }
+                        if !matches!(c, '\'' | '"' | '\\') {
+                            delimiter.push(c);
+                        }
+                        idx += 1;
+                    }
+                    // Skips shifts like `$((1 << 2))`.
+                    if delimiter.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                        self.heredocs.push_back(Heredoc { delimiter, strip_tabs });
+                    }
+                    scan.trailing_op = None;
+                    continue;
+                }
+                _ => {
+                    word.push(c);
+                    scan.trailing_op = None;
+                }
+            }
+            idx += 1;
+        }
+        if self.quote.is_none() && !word.is_empty() {
+            let keyword = if word_quoted { "" } else { word.as_str() };
+            self.word(keyword, &mut command_start, &mut leading, &mut level);
+            scan.trailing_op = None;
+        }
+        self.set_level(&mut leading, &mut level);
+        scan.level = level.unwrap_or_default();
+        scan
+    }
+
+    fn set_level(&self, leading: &mut bool, level: &mut Option<usize>) {
+        *leading = false;
+        level.get_or_insert_with(|| self.depth());
+    }
+
+    /// Applies a word, `keyword` is empty if the word is quoted.
+    fn word(
+        &mut self,
+        keyword: &str,
+        command_start: &mut bool,
+        leading: &mut bool,
+        level: &mut Option<usize>,
+    ) {
+        let top = self.stack.last().copied();
+        if top == Some(Block::CaseHead) {
+            if keyword == "in" {
+                self.stack.pop();
+                self.stack.push(Block::Case);
+            }
+            return;
+        }
+        if top == Some(Block::Case) && keyword != "esac" {
+            // Part of a `case` pattern.
+            self.set_level(leading, level);
+            return;
+        }
+        // Braces are only reserved words at the start of a command, but a lone
+        // brace is rarely anything else, e.g. in `function name {`.
+        if !*command_start && !matches!(keyword, "{" | "}") {
+            self.set_level(leading, level);
+            return;
+        }
+        match keyword {
+            "fi" | "done" | "}" | "esac" | "elif" | "else" => {
+                match keyword {
+                    "}" => self.pop(Block::Brace),
+                    "esac" => {
+                        self.pop_case_item();
+                        self.pop(Block::Case);
+                    }
+                    _ => self.pop(Block::Keyword),
+                }
+                self.set_level(leading, level);
+                if keyword == "else" {
+                    self.stack.push(Block::Keyword);
+                }
+                *command_start = matches!(keyword, "elif" | "else");
+            }
+            "then" | "do" | "{" => {
+                self.set_level(leading, level);
+                self.stack.push(if keyword == "{" { Block::Brace } else { Block::Keyword });
+            }
+            "case" => {
+                self.set_level(leading, level);
+                self.stack.push(Block::CaseHead);
+                *command_start = false;
+            }
+            "if" | "while" | "until" | "!" | "time" => self.set_level(leading, level),
+            _ => {
+                self.set_level(leading, level);
+                *command_start = false;
+            }
+        }
+    }
+}
+
+/// Returns the index after the quote closing the string that starts at
+/// `start`, or `None` if the string continues on the next line.
+fn close_quote(chars: &[(usize, char)], start: usize, quote: Quote) -> Option<usize> {
+    let closing = match quote {
+        Quote::Single | Quote::AnsiC => '\'',
+        Quote::Double => '"',
+        Quote::Backtick => '`',
+    };
+    let mut idx = start;
+    while let Some(&(_, c)) = chars.get(idx) {
+        if c == '\\' && quote != Quote::Single {
+            idx += 2;
+            continue;
+        }
+        if c == closing {
+            return Some(idx + 1);
+        }
+        idx += 1;
+    }
+    None
+}
+
+/// Returns the index after the brace closing a parameter expansion.
+fn close_brace(chars: &[(usize, char)], start: usize) -> usize {
+    let mut depth = 1;
+    let mut idx = start;
+    while let Some(&(_, c)) = chars.get(idx) {
+        match c {
+            '\\' => idx += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return idx + 1;
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+    idx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        Options::parse(args).unwrap().0
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args = ["-ln", "auto", "-i", "4", "-ci", "-bn", "-w", "-sr"];
+        let (options, ignored) = Options::parse(&args).unwrap();
+        assert_eq!(options, Options { indent: 4, case_indent: true, binary_next_line: true });
+        assert_eq!(ignored, vec!["-sr"]);
+        assert!(Options::parse(&["-i", "x"]).is_err());
+        assert!(Options::parse(&["-ln", "zsh"]).is_err());
+    }
+
+    #[test]
+    fn test_format_indents_blocks() {
+        let source = r#"#!/usr/bin/env bash
+main() {
+if [[ -n "$1" ]]; then
+echo "${1:-}"
+elif true
+then
+  for x in a b; do
+      echo "$x" | tr a b
+  done
+else
+echo none
+fi
+
+
+local list=(
+a
+b
+)
+}
+"#;
+        let expected = r#"#!/usr/bin/env bash
+main() {
+    if [[ -n "$1" ]]; then
+        echo "${1:-}"
+    elif true
+    then
+        for x in a b; do
+            echo "$x" | tr a b
+        done
+    else
+        echo none
+    fi
+
+    local list=(
+        a
+        b
+    )
+}
+"#;
+        assert_eq!(format(source, &options(&["-i", "4"])), expected);
+    }
+
+    #[test]
+    fn test_format_case() {
+        let source = "case \"$1\" in\na | b)\necho ab\n;;\n*) echo other ;;\nesac\n";
+        let nested = "case $1 in\na)\ncase $2 in\nb) echo b ;;\nesac\n;;\nesac\n";
+        let expected = "case \"$1\" in\na | b)\n\techo ab\n\t;;\n*) echo other ;;\nesac\n";
+        assert_eq!(format(source, &options(&[])), expected);
+        let expected = "case \"$1\" in\n  a | b)\n    echo ab\n    ;;\n  *) echo other ;;\nesac\n";
+        assert_eq!(format(source, &options(&["-i", "2", "-ci"])), expected);
+        let expected =
+            "case $1 in\n  a)\n    case $2 in\n      b) echo b ;;\n    esac\n    ;;\nesac\n";
+        assert_eq!(format(nested, &options(&["-i", "2", "-ci"])), expected);
+    }
+
+    #[test]
+    fn test_format_keeps_heredocs_and_strings() {
+        let source = "if true; then\ncat <<-EOF\n  keep\n\tEOF\necho 'a\n  b'\nfi\n";
+        let expected = "if true; then\n  cat <<-EOF\n  keep\n\tEOF\n  echo 'a\n  b'\nfi\n";
+        assert_eq!(format(source, &options(&["-i", "2"])), expected);
+    }
+
+    #[test]
+    fn test_format_binary_next_line() {
+        let source = "true &&\nfalse ||\necho done\necho a \\\nb\nfunction f {\nf() { :; }\n}\n";
+        let expected = "true \\\n  && false \\\n  || echo done\necho a \\\n  b\nfunction f {\n  f() { :; }\n}\n";
+        assert_eq!(format(source, &options(&["-i", "2", "-bn"])), expected);
+        let expected =
+            "true &&\n  false ||\n  echo done\necho a \\\n  b\nfunction f {\n  f() { :; }\n}\n";
+        assert_eq!(format(source, &options(&["-i", "2"])), expected);
+    }
+}
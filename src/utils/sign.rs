@@ -0,0 +1,292 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_compact::SecretKey;
+use which::which;
+
+use crate::utils::path::add_extension;
+
+/// The algorithm of minisign keys.
+const KEY_ALGORITHM: &[u8] = b"Ed";
+/// The algorithm of minisign signatures over the BLAKE2b hash of the file.
+const PREHASHED_ALGORITHM: &[u8] = b"ED";
+const KDF_NONE: &[u8] = &[0, 0];
+const KDF_SCRYPT: &[u8] = b"Sc";
+/// The length of a decoded minisign secret key.
+const SECRET_KEY_LEN: usize = 158;
+/// The namespace of SSH signatures, the one `ssh-keygen` uses for files.
+const SSH_NAMESPACE: &str = "file";
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignMode {
+    Minisign,
+    Ssh,
+}
+
+impl SignMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Minisign => "minisign",
+            Self::Ssh => "ssh",
+        }
+    }
+
+    /// Returns the extension of the signature files.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Minisign => "minisig",
+            Self::Ssh => "sig",
+        }
+    }
+}
+
+/// A minisign secret key, as created by `minisign -G`.
+pub struct MinisignKey {
+    key_id: [u8; 8],
+    secret: SecretKey,
+}
+
+impl MinisignKey {
+    /// Decodes the content of a secret key file, `password` is only called if
+    /// the key is encrypted.
+    pub fn decode<F>(text: &str, password: F) -> anyhow::Result<Self>
+    where
+        F: FnOnce() -> anyhow::Result<String>,
+    {
+        let encoded = text
+            .lines()
+            .map(str::trim)
+            .find(|x| !x.is_empty() && !x.starts_with("untrusted comment:"))
+            .context("empty minisign secret key")?;
+        let bytes = STANDARD.decode(encoded).context("invalid minisign secret key")?;
+        if bytes.len() != SECRET_KEY_LEN || &bytes[..2] != KEY_ALGORITHM {
+            anyhow::bail!("invalid minisign secret key");
+        }
+        let mut keynum = bytes[54..].to_vec();
+        match &bytes[2..4] {
+            KDF_NONE => {}
+            KDF_SCRYPT => {
+                let opslimit = u64::from_le_bytes(bytes[38..46].try_into()?);
+                let memlimit = u64::from_le_bytes(bytes[46..54].try_into()?);
+                let params = scrypt_params(opslimit, memlimit)?;
+                let mut stream = vec![0; keynum.len()];
+                scrypt::scrypt(password()?.as_bytes(), &bytes[6..38], &params, &mut stream)
+                    .map_err(|e| {
+                        anyhow::format_err!("failed to decrypt minisign secret key: {e}")
+                    })?;
+                keynum.iter_mut().zip(stream).for_each(|(x, k)| *x ^= k);
+            }
+            _ => anyhow::bail!("unsupported key derivation in minisign secret key"),
+        }
+        let key_id: [u8; 8] = keynum[..8].try_into()?;
+        let secret = SecretKey::from_slice(&keynum[8..72])?;
+        if key_checksum(&key_id, &secret) != keynum[72..] {
+            anyhow::bail!("wrong password or corrupted minisign secret key");
+        }
+        Ok(Self { key_id, secret })
+    }
+
+    /// Returns the public key in the format of `minisign -P`.
+    pub fn public_key(&self) -> String {
+        let mut bytes = KEY_ALGORITHM.to_vec();
+        bytes.extend(self.key_id);
+        bytes.extend(self.secret.public_key().as_ref());
+        STANDARD.encode(bytes)
+    }
+
+    /// Signs `data` and returns the content of the `.minisig` file.
+    ///
+    /// Ed25519 signatures are deterministic, so the output only depends on
+    /// `data` and `trusted_comment`.
+    pub fn sign(&self, data: &[u8], trusted_comment: &str) -> String {
+        let signature = self.secret.sign(blake2b_simd::blake2b(data).as_bytes(), None);
+        let mut global = signature.to_vec();
+        global.extend(trusted_comment.as_bytes());
+        let global_signature = self.secret.sign(global, None);
+        let mut bytes = PREHASHED_ALGORITHM.to_vec();
+        bytes.extend(self.key_id);
+        bytes.extend(signature.as_ref());
+        format!(
+            "untrusted comment: signature from shinc secret key\n{}\ntrusted comment: {trusted_comment}\n{}\n",
+            STANDARD.encode(bytes),
+            STANDARD.encode(global_signature.as_ref())
+        )
+    }
+}
+
+/// Returns the checksum stored in minisign secret keys.
+fn key_checksum(key_id: &[u8], secret: &SecretKey) -> Vec<u8> {
+    let mut state = blake2b_simd::Params::new().hash_length(32).to_state();
+    state.update(KEY_ALGORITHM).update(key_id).update(secret.as_ref());
+    state.finalize().as_bytes().to_vec()
+}
+
+/// Converts the libsodium limits stored in minisign keys to scrypt parameters.
+fn scrypt_params(opslimit: u64, memlimit: u64) -> anyhow::Result<scrypt::Params> {
+    let opslimit = opslimit.max(32768);
+    let r = 8;
+    let max_n = if opslimit < memlimit / 32 { opslimit / (r * 4) } else { memlimit / (r * 128) };
+    let log_n = (1..63).find(|n| 1u64 << n > max_n / 2).unwrap_or(63);
+    let p = if opslimit < memlimit / 32 {
+        1
+    } else {
+        let max_rp = ((opslimit / 4) >> log_n).min(0x3fff_ffff);
+        (max_rp / r).max(1)
+    };
+    scrypt::Params::new(log_n as u8, r as u32, p as u32, scrypt::Params::RECOMMENDED_LEN)
+        .map_err(|e| anyhow::format_err!("invalid scrypt parameters in minisign secret key: {e}"))
+}
+
+/// Checks a minisign `signature` of `data`. `public_key` is either the
+/// base64 key or the content of a `.pub` file.
+pub fn verify_minisign(public_key: &str, data: &[u8], signature: &str) -> anyhow::Result<()> {
+    let public_key = if public_key.trim().contains('\n') {
+        minisign_verify::PublicKey::decode(public_key)?
+    } else {
+        minisign_verify::PublicKey::from_base64(public_key.trim())?
+    };
+    let signature = minisign_verify::Signature::decode(signature)?;
+    public_key.verify(data, &signature, false)?;
+    Ok(())
+}
+
+/// Returns the base64 key of a minisign `public_key`, which is either the
+/// key itself or the content of a `.pub` file with its comment line.
+pub fn minisign_key_line(public_key: &str) -> &str {
+    public_key
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+        .unwrap_or_default()
+}
+
+/// Signs `path` with `ssh-keygen -Y sign`, which writes the signature next to
+/// it with a `.sig` extension.
+pub fn ssh_sign<P: AsRef<Path>, K: AsRef<Path>>(path: P, key: K) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let signature = add_extension(path, SignMode::Ssh.extension())?;
+    // ssh-keygen refuses to overwrite an existing signature.
+    if signature.exists() {
+        fs::remove_file(&signature)?;
+    }
+    let mut command = ssh_keygen()?;
+    command.args(["-Y", "sign", "-n", SSH_NAMESPACE, "-f"]).arg(key.as_ref()).arg(path);
+    run_ssh_keygen(command, None)
+}
+
+/// Checks the SSH `signature` file of `path`, signed by `identity` with the
+/// key `public_key`, e.g. `ssh-ed25519 AAAA...`.
+pub fn ssh_verify<P: AsRef<Path>, S: AsRef<Path>>(
+    path: P,
+    signature: S,
+    identity: &str,
+    public_key: &str,
+) -> anyhow::Result<()> {
+    // The scratch directory is removed when dropped, errors included.
+    let dir = tempfile::TempDir::with_prefix("shinc-verify-")?;
+    let signers = dir.path().join("allowed_signers");
+    fs::write(&signers, format!("{identity} {}\n", public_key.trim()))?;
+    let mut command = ssh_keygen()?;
+    command
+        .args(["-Y", "verify", "-n", SSH_NAMESPACE, "-I", identity, "-f"])
+        .arg(&signers)
+        .arg("-s")
+        .arg(signature.as_ref());
+    run_ssh_keygen(command, Some(path.as_ref()))
+}
+
+fn ssh_keygen() -> anyhow::Result<Command> {
+    let Ok(cmd) = which("ssh-keygen") else {
+        anyhow::bail!("ssh-keygen is not installed or not in PATH.")
+    };
+    Ok(Command::new(cmd))
+}
+
+fn run_ssh_keygen(mut command: Command, stdin: Option<&Path>) -> anyhow::Result<()> {
+    if let Some(path) = stdin {
+        command.stdin(fs::File::open(path)?);
+    }
+    let output = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|err| anyhow::format_err!("failed to execute ssh-keygen: {err}"))?;
+    if !output.status.success() {
+        anyhow::bail!("ssh-keygen failed with error:\n{}", String::from_utf8_lossy(&output.stderr))
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_compact::{KeyPair, Seed};
+
+    use super::*;
+
+    /// Encodes an unencrypted secret key like `minisign -G -W`.
+    fn encode_key(seed: u8) -> String {
+        encode(&key_bytes(seed))
+    }
+
+    fn encode(bytes: &[u8]) -> String {
+        format!("untrusted comment: minisign secret key\n{}\n", STANDARD.encode(bytes))
+    }
+
+    fn key_bytes(seed: u8) -> Vec<u8> {
+        let secret = KeyPair::from_seed(Seed::new([seed; 32])).sk;
+        let key_id = [seed; 8];
+        let mut bytes = KEY_ALGORITHM.to_vec();
+        bytes.extend(KDF_NONE);
+        bytes.extend(b"B2");
+        bytes.extend([0; 48]);
+        bytes.extend(key_id);
+        bytes.extend(secret.as_ref());
+        bytes.extend(key_checksum(&key_id, &secret));
+        bytes
+    }
+
+    #[test]
+    fn test_minisign_sign_verify() {
+        let key = MinisignKey::decode(&encode_key(1), || unreachable!()).unwrap();
+        let comment = "timestamp:0\tfile:a.tar.gz\thashed";
+        let signature = key.sign(b"hello", comment);
+        assert_eq!(signature, key.sign(b"hello", comment));
+        assert!(signature.contains(&format!("\ntrusted comment: {comment}\n")));
+
+        verify_minisign(&key.public_key(), b"hello", &signature).unwrap();
+        assert!(verify_minisign(&key.public_key(), b"hellO", &signature).is_err());
+        let other = MinisignKey::decode(&encode_key(2), || unreachable!()).unwrap();
+        assert!(verify_minisign(&other.public_key(), b"hello", &signature).is_err());
+    }
+
+    #[test]
+    fn test_minisign_key_line() {
+        let key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+        assert_eq!(minisign_key_line(key), key);
+        let file = format!("untrusted comment: minisign public key 37\n{key}\n");
+        assert_eq!(minisign_key_line(&file), key);
+        assert_eq!(minisign_key_line("untrusted comment: x\n"), "");
+    }
+
+    #[test]
+    fn test_minisign_decode_invalid_key() {
+        assert!(MinisignKey::decode("untrusted comment: x\n", || unreachable!()).is_err());
+        let mut bytes = key_bytes(1);
+        bytes[70] ^= 1;
+        assert!(MinisignKey::decode(&encode(&bytes), || unreachable!()).is_err());
+        bytes[3] = b'x';
+        assert!(MinisignKey::decode(&encode(&bytes), || unreachable!()).is_err());
+    }
+
+    #[test]
+    fn test_scrypt_params() {
+        // The default limits of `minisign -G`.
+        let params = scrypt_params(33554432, 1073741824).unwrap();
+        assert_eq!((params.log_n(), params.r(), params.p()), (20, 8, 1));
+    }
+}
@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::Path;
+
+use crate::utils::fs::create_file;
+
+/// The version of the source map format.
+const SOURCE_MAP_VERSION: u32 = 1;
+
+/// How many lines ahead to search when aligning a formatted line with the
+/// compiled line it came from.
+const MAX_LOOKAHEAD: usize = 64;
+
+/// An index into [`SourceMap::sources`] and a 1-based line number.
+pub type Mapping = (usize, usize);
+
+/// Maps every line of a built script back to the source line it was
+/// compiled from.
+///
+/// Lines without a mapping (e.g. generated by `argc`) are stored as `null`.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    version: u32,
+    file: String,
+    sources: Vec<String>,
+    mappings: Vec<Option<Mapping>>,
+}
+
+/// A resolved position in a source file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub source: &'a str,
+    pub line: usize,
+}
+
+#[allow(dead_code)]
+impl SourceMap {
+    pub fn new(file: &str) -> Self {
+        Self { version: SOURCE_MAP_VERSION, file: file.to_string(), ..Default::default() }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        serde_json::to_writer(create_file(path)?, self)?;
+        Ok(())
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn len(&self) -> usize {
+        self.mappings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// Registers a source file and returns its index.
+    pub fn add_source(&mut self, source: &str) -> usize {
+        match self.sources.iter().position(|s| s == source) {
+            Some(idx) => idx,
+            None => {
+                self.sources.push(source.to_string());
+                self.sources.len() - 1
+            }
+        }
+    }
+
+    /// Appends the mapping of the next output line.
+    pub fn push(&mut self, mapping: Option<Mapping>) {
+        self.mappings.push(mapping);
+    }
+
+    /// Looks up the origin of the 1-based output `line`.
+    pub fn lookup(&self, line: usize) -> Option<Location<'_>> {
+        let (idx, source_line) = (*self.mappings.get(line.checked_sub(1)?)?)?;
+        let source = self.sources.get(idx)?;
+        Some(Location { source, line: source_line })
+    }
+
+    /// Builds the source map of `output`, a transformed version of
+    /// `compiled` whose lines are described by `self`.
+    ///
+    /// Formatting and `argc` building keep the order of lines but may change
+    /// their whitespace, drop blank lines or insert generated code, so lines
+    /// are aligned by their content with whitespace ignored.
+    pub fn remap(&self, compiled: &str, output: &str) -> Self {
+        let compiled: Vec<_> = compiled.lines().map(normalize).collect();
+        let mut map = Self {
+            version: self.version,
+            file: self.file.clone(),
+            sources: self.sources.clone(),
+            mappings: vec![],
+        };
+        let mut cursor = 0;
+        for line in output.lines().map(normalize) {
+            let found = if line.is_empty() {
+                None
+            } else {
+                compiled[cursor..].iter().take(MAX_LOOKAHEAD).position(|x| *x == line)
+            };
+            match found {
+                Some(offset) => {
+                    map.push(self.mappings.get(cursor + offset).copied().flatten());
+                    cursor += offset + 1;
+                }
+                None => map.push(None),
+            }
+        }
+        map
+    }
+}
+
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SourceMap {
+        let mut map = SourceMap::new("demo");
+        let main = map.add_source("src/main.sh");
+        let lib = map.add_source("src/lib.sh");
+        map.push(Some((main, 1)));
+        map.push(Some((lib, 1)));
+        map.push(Some((lib, 2)));
+        map.push(Some((lib, 3)));
+        map.push(None);
+        map
+    }
+
+    #[test]
+    fn test_add_source_dedup() {
+        let mut map = SourceMap::new("demo");
+        assert_eq!(map.add_source("src/main.sh"), 0);
+        assert_eq!(map.add_source("src/lib.sh"), 1);
+        assert_eq!(map.add_source("src/main.sh"), 0);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let map = sample();
+        assert_eq!(map.lookup(3), Some(Location { source: "src/lib.sh", line: 2 }));
+        assert_eq!(map.lookup(0), None);
+        assert_eq!(map.lookup(5), None);
+        assert_eq!(map.lookup(6), None);
+    }
+
+    #[test]
+    fn test_remap() {
+        let compiled = "#!/bin/bash\nfoo() {\n  echo  foo\n}\n\n";
+        let output = "#!/bin/bash\nfoo() {\n    echo foo\n}\n# generated\n_argc_run\n";
+        let map = sample().remap(compiled, output);
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.lookup(1), Some(Location { source: "src/main.sh", line: 1 }));
+        assert_eq!(map.lookup(3), Some(Location { source: "src/lib.sh", line: 2 }));
+        assert_eq!(map.lookup(4), Some(Location { source: "src/lib.sh", line: 3 }));
+        assert_eq!(map.lookup(5), None);
+        assert_eq!(map.lookup(6), None);
+    }
+}
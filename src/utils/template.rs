@@ -0,0 +1,133 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::config::CONFIG_SUBDIR;
+use crate::include_template;
+use crate::utils::path::expand_tilde;
+
+/// The sub-directory of the configuration directories where templates
+/// overriding the embedded ones are looked up.
+const TEMPLATES_SUBDIR: &str = "templates";
+
+/// The templates embedded in shinc, by name.
+pub const EMBEDDED_TEMPLATES: [(&str, &str); 11] = [
+    ("arch/PKGBUILD", include_template!("arch/PKGBUILD")),
+    ("config/default.toml", include_template!("config/default.toml")),
+    ("homebrew/formula.rb", include_template!("homebrew/formula.rb")),
+    ("nix/default.nix", include_template!("nix/default.nix")),
+    ("nix/flake.nix", include_template!("nix/flake.nix")),
+    ("project/LICENSE-MIT", include_template!("project/LICENSE-MIT")),
+    ("project/README.md", include_template!("project/README.md")),
+    ("project/gitignore", include_template!("project/gitignore")),
+    ("project/lib/common.sh", include_template!("project/lib/common.sh")),
+    ("project/main.sh", include_template!("project/main.sh")),
+    ("shell/install.sh", include_template!("shell/install.sh")),
+];
+
+/// Returns the directories searched for override templates, the project one
+/// under `root` first, then the user one.
+pub fn override_dirs<P: AsRef<Path>>(root: P) -> [PathBuf; 2] {
+    [
+        root.as_ref().join(CONFIG_SUBDIR).join(TEMPLATES_SUBDIR),
+        expand_tilde("~").join(CONFIG_SUBDIR).join(TEMPLATES_SUBDIR),
+    ]
+}
+
+/// Returns the embedded template `name`.
+pub fn embedded(name: &str) -> Option<&'static str> {
+    EMBEDDED_TEMPLATES.iter().find(|(x, _)| *x == name).map(|(_, content)| *content)
+}
+
+/// Returns the first override of the template `name` in `dirs`.
+pub fn find_override<P: AsRef<Path>>(dirs: &[P], name: &str) -> Option<PathBuf> {
+    dirs.iter().map(|dir| dir.as_ref().join(name)).find(|path| path.is_file())
+}
+
+/// Returns the content of the template `name`, from the project or user
+/// templates directory if it is overridden there, or the embedded one.
+pub fn load<P: AsRef<Path>>(root: P, name: &str) -> anyhow::Result<Cow<'static, str>> {
+    let Some(content) = embedded(name) else {
+        anyhow::bail!("unknown template '{name}'");
+    };
+    match find_override(&override_dirs(root), name) {
+        Some(path) => {
+            log::debug!("Using template '{}'", path.display());
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read template '{}'", path.display()))?;
+            Ok(Cow::Owned(content))
+        }
+        None => Ok(Cow::Borrowed(content)),
+    }
+}
+
+/// Returns an engine with the templates `names`, see [`load`].
+pub fn engine<P: AsRef<Path>>(root: P, names: &[&str]) -> anyhow::Result<tera::Tera> {
+    let mut engine = new_engine();
+    for name in names {
+        engine.add_raw_template(name, &load(&root, name)?)?;
+    }
+    Ok(engine)
+}
+
+/// Returns an engine with the filters escaping strings for the generated
+/// files: `bash_string` for the PKGBUILD and `nix_string` for Nix.
+fn new_engine() -> tera::Tera {
+    let mut engine = tera::Tera::default();
+    engine.register_filter("bash_string", bash_string);
+    engine.register_filter("nix_string", nix_string);
+    engine
+}
+
+/// Escapes `value` to be placed in a double-quoted bash string.
+fn bash_string(value: &str, _: tera::Kwargs, _: &tera::State) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes `value` to be placed in a double-quoted Nix string, where `${`
+/// starts an interpolation.
+fn nix_string(value: &str, _: tera::Kwargs, _: &tera::State) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace("${", "\\${")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::create_file;
+
+    #[test]
+    fn test_embedded_templates() {
+        let names: Vec<_> = EMBEDDED_TEMPLATES.iter().map(|(name, _)| *name).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(names, sorted);
+        for (name, content) in EMBEDDED_TEMPLATES {
+            new_engine().add_raw_template(name, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_override() {
+        let root = std::env::temp_dir().join("test_load_override");
+        let [project_dir, _] = override_dirs(&root);
+        let path = project_dir.join("homebrew/formula.rb");
+        std::io::Write::write_all(&mut create_file(&path).unwrap(), b"custom").unwrap();
+
+        assert_eq!(find_override(&[&project_dir], "homebrew/formula.rb"), Some(path.clone()));
+        assert_eq!(load(&root, "homebrew/formula.rb").unwrap(), "custom");
+        assert!(load(&root, "missing").is_err());
+
+        fs::remove_dir_all(root).unwrap();
+        assert_eq!(find_override(&[&project_dir], "homebrew/formula.rb"), None);
+    }
+}
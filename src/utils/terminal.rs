@@ -30,8 +30,13 @@ use dialoguer::Confirm;
 /// ```
 #[allow(dead_code)]
 pub fn confirm_or_exit(message: &str) {
-    let confirmation = Confirm::new().with_prompt(message).default(true).interact().unwrap();
-    if !confirmation {
+    if !confirm(message).unwrap() {
         std::process::exit(0);
     }
 }
+
+/// Prompts the user with a confirmation message, defaulting to yes, and
+/// returns the answer.
+pub fn confirm(message: &str) -> anyhow::Result<bool> {
+    Ok(Confirm::new().with_prompt(message).default(true).interact()?)
+}
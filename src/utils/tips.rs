@@ -1,35 +1,105 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+
 use colored::Color;
 
 use super::formatter::Formatter;
 
+thread_local! {
+    /// Lines captured by [`capture`] on the current thread.
+    static CAPTURED: RefCell<Option<Vec<Line>>> = const { RefCell::new(None) };
+}
+
+/// A line of output along with the stream it goes to.
+#[derive(Debug)]
+enum Line {
+    Stdout(String),
+    Stderr(String),
+}
+
+impl Line {
+    fn print(&self) {
+        match self {
+            Self::Stdout(text) => println!("{text}"),
+            Self::Stderr(text) => eprintln!("{text}"),
+        }
+    }
+}
+
+/// Output captured by [`capture`].
+#[derive(Debug, Default)]
+pub struct Captured(Vec<Line>);
+
+impl Captured {
+    /// Prints the captured lines to the streams they were meant for.
+    pub fn print(&self) {
+        self.0.iter().for_each(Line::print);
+    }
+}
+
+/// Runs `f`, capturing what it prints through this module instead of
+/// printing it.
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Captured) {
+    let previous = CAPTURED.replace(Some(vec![]));
+    let result = f();
+    let lines = CAPTURED.replace(previous).unwrap_or_default();
+    (result, Captured(lines))
+}
+
+fn out(text: impl Display) {
+    emit(Line::Stdout(text.to_string()));
+}
+
+fn err(text: impl Display) {
+    emit(Line::Stderr(text.to_string()));
+}
+
+fn emit(line: Line) {
+    let line = CAPTURED.with_borrow_mut(|captured| match captured {
+        Some(lines) => {
+            lines.push(line);
+            None
+        }
+        None => Some(line),
+    });
+    if let Some(line) = line {
+        line.print();
+    }
+}
+
+#[allow(dead_code)]
+pub fn print(text: &str) {
+    out(text);
+}
+
 #[allow(dead_code)]
 pub fn title(text: &str) {
-    println!("{}", Formatter::new().headline(text).color(Color::Green));
+    out(Formatter::new().headline(text).color(Color::Green));
 }
 
 #[allow(dead_code)]
 pub fn h1(text: &str) {
-    println!("{}", Formatter::new().headline(text).color(Color::Blue));
+    out(Formatter::new().headline(text).color(Color::Blue));
 }
 
 #[allow(dead_code)]
 pub fn h2(text: &str) {
-    println!("{}", Formatter::new().indent("  ").headline(text).color(Color::Blue));
+    out(Formatter::new().indent("  ").headline(text).color(Color::Blue));
 }
 
 #[allow(dead_code)]
 pub fn debug(message: &str) {
-    eprintln!("{}", Formatter::new().headline(message).color(Color::Magenta));
+    err(Formatter::new().headline(message).color(Color::Magenta));
 }
 
 #[allow(dead_code)]
 pub fn warning(message: &str) {
-    eprintln!("{}", Formatter::new().warning("Warning").text(message));
+    err(Formatter::new().warning("Warning").text(message));
 }
 
 #[allow(dead_code)]
 pub fn error(message: &str) {
-    eprintln!("{}", Formatter::new().error("Error").text(message));
+    err(Formatter::new().error("Error").text(message));
 }
 
 #[allow(dead_code)]
@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use git2::{IndexAddOption, Repository};
+use git2::{BranchType, IndexAddOption, Oid, Repository, ResetType, StatusOptions};
 
 use crate::git_ref_tag;
 
@@ -22,6 +22,16 @@ impl Repo {
         Ok(Self { inner: repo })
     }
 
+    pub fn init<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let repo = Repository::init(path)?;
+        Ok(Self { inner: repo })
+    }
+
+    pub fn remote_url(&self, name: &str) -> anyhow::Result<Option<String>> {
+        let remote = self.inner.find_remote(name)?;
+        Ok(remote.url().ok().map(str::to_string))
+    }
+
     pub fn tag_exists(&self, tag_name: &str) -> anyhow::Result<bool, git2::Error> {
         let r = self.inner.revparse_single(&git_ref_tag!(tag_name));
         match r {
@@ -36,12 +46,66 @@ impl Repo {
         }
     }
 
+    /// Returns the `v*` tag with the highest semantic version, and the version.
+    pub fn latest_version_tag(&self) -> anyhow::Result<Option<(String, semver::Version)>> {
+        let names = self.inner.tag_names(Some("v*"))?;
+        let mut latest: Option<(String, semver::Version)> = None;
+        for name in names.iter() {
+            let Some(name) = name? else {
+                continue;
+            };
+            let Ok(version) = semver::Version::parse(&name[1..]) else {
+                continue;
+            };
+            if latest.as_ref().is_none_or(|(_, v)| version > *v) {
+                latest = Some((name.to_string(), version));
+            }
+        }
+        Ok(latest)
+    }
+
     pub fn current_branch(&self) -> anyhow::Result<String> {
         let head = self.inner.head()?;
         let branch = head.shorthand().unwrap();
         Ok(branch.to_string())
     }
 
+    /// Returns the paths of the tracked files with uncommitted changes,
+    /// relative to the work tree.
+    pub fn changed_files(&self) -> anyhow::Result<Vec<String>> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(false).include_ignored(false);
+        let statuses = self.inner.statuses(Some(&mut opts))?;
+        let mut files = vec![];
+        for entry in statuses.iter() {
+            files.push(entry.path()?.to_string());
+        }
+        Ok(files)
+    }
+
+    /// Returns the upstream of the branch `name` and how many of its commits
+    /// the branch is missing, or `None` if the branch tracks no upstream.
+    pub fn behind_upstream(&self, name: &str) -> anyhow::Result<Option<(String, usize)>> {
+        let branch = self.inner.find_branch(name, BranchType::Local)?;
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let upstream_name = upstream.name()?.unwrap_or_default().to_string();
+        let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) else {
+            return Ok(None);
+        };
+        let (_, behind) = self.inner.graph_ahead_behind(local, remote)?;
+        Ok(Some((upstream_name, behind)))
+    }
+
+    /// Returns the commit time of `HEAD` in seconds since the Unix epoch.
+    pub fn head_commit_time(&self) -> anyhow::Result<i64> {
+        let commit = self.inner.head()?.peel_to_commit()?;
+        Ok(commit.time().seconds())
+    }
+
     pub fn commit<P: AsRef<Path>>(&self, files: &[P], message: &str) -> anyhow::Result<()> {
         let mut index = self.inner.index()?;
         for file in files {
@@ -68,10 +132,39 @@ impl Repo {
         Ok(())
     }
 
+    /// Returns the id of the commit of `HEAD`.
+    pub fn head_id(&self) -> anyhow::Result<Oid> {
+        Ok(self.inner.head()?.peel_to_commit()?.id())
+    }
+
+    /// Moves `HEAD` and the index to the commit `id`, keeping the work tree.
+    pub fn reset_mixed(&self, id: Oid) -> anyhow::Result<()> {
+        let commit = self.inner.find_object(id, None)?;
+        self.inner.reset(&commit, ResetType::Mixed, None)?;
+        Ok(())
+    }
+
     pub fn create_tag(&self, tag_name: &str, message: &str) -> anyhow::Result<()> {
         let head = self.inner.head()?;
         let commit = head.peel_to_commit()?;
         self.inner.tag(tag_name, commit.as_object(), &self.inner.signature()?, message, false)?;
         Ok(())
     }
+
+    pub fn delete_tag(&self, tag_name: &str) -> anyhow::Result<()> {
+        self.inner.tag_delete(tag_name)?;
+        Ok(())
+    }
+}
+
+/// Returns the value of `key` from the global git configuration, if set.
+#[allow(dead_code)]
+pub fn config_string(key: &str) -> Option<String> {
+    git2::Config::open_default().and_then(|c| c.get_string(key)).ok()
+}
+
+/// Returns `true` if `path` is inside a git work tree.
+#[allow(dead_code)]
+pub fn is_inside_work_tree<P: AsRef<Path>>(path: P) -> bool {
+    Repository::discover(path).is_ok()
 }
@@ -0,0 +1,144 @@
+use std::str::FromStr;
+
+use semver::{Prerelease, Version};
+
+/// A semantic version component to increment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// Returns the highest component that differs between `from` and `to`,
+    /// `None` if their release versions are the same.
+    pub fn between(from: &Version, to: &Version) -> Option<Self> {
+        if to.major != from.major {
+            Some(Bump::Major)
+        } else if to.minor != from.minor {
+            Some(Bump::Minor)
+        } else if to.patch != from.patch {
+            Some(Bump::Patch)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `version` incremented by `self`. As with npm, a pre-release is
+    /// released first if it already is the version of this increment, e.g.
+    /// `1.0.0-rc.1` becomes `1.0.0` with a major bump.
+    pub fn apply(self, version: &Version) -> Version {
+        let is_pre = !version.pre.is_empty();
+        let (major, minor, patch) = (version.major, version.minor, version.patch);
+        match self {
+            Bump::Major if is_pre && minor == 0 && patch == 0 => Version::new(major, 0, 0),
+            Bump::Major => Version::new(major + 1, 0, 0),
+            Bump::Minor if is_pre && patch == 0 => Version::new(major, minor, 0),
+            Bump::Minor => Version::new(major, minor + 1, 0),
+            Bump::Patch if is_pre => Version::new(major, minor, patch),
+            Bump::Patch => Version::new(major, minor, patch + 1),
+        }
+    }
+}
+
+/// The version argument of `shinc release`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VersionSpec {
+    /// An explicit version.
+    Exact(Version),
+    /// A bump inferred from the conventional commits since the last release.
+    Auto,
+    /// An explicit bump.
+    Bump(Bump),
+    /// A pre-release, whose identifier is given separately.
+    Pre,
+}
+
+impl FromStr for VersionSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "auto" => VersionSpec::Auto,
+            "major" => VersionSpec::Bump(Bump::Major),
+            "minor" => VersionSpec::Bump(Bump::Minor),
+            "patch" => VersionSpec::Bump(Bump::Patch),
+            "pre" => VersionSpec::Pre,
+            _ => VersionSpec::Exact(Version::parse(s).map_err(|_| {
+                anyhow::format_err!(
+                    "need semantic version number, or one of auto, major, minor, patch, pre"
+                )
+            })?),
+        })
+    }
+}
+
+/// Returns the next `id` pre-release of `version`: its next number if it
+/// already is an `id` pre-release, e.g. `1.0.0-rc.1` becomes `1.0.0-rc.2`,
+/// otherwise the first one of `version` incremented by `bump`.
+pub fn pre_release<F>(version: &Version, id: &str, bump: F) -> anyhow::Result<Version>
+where
+    F: FnOnce() -> anyhow::Result<Bump>,
+{
+    let number = version
+        .pre
+        .as_str()
+        .strip_prefix(id)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|n| n.parse::<u64>().ok());
+    let (mut next, number) = match number {
+        Some(n) => (version.clone(), n + 1),
+        None => (bump()?.apply(version), 1),
+    };
+    next.pre = Prerelease::new(&format!("{id}.{number}"))
+        .map_err(|_| anyhow::format_err!("invalid pre-release identifier '{id}'"))?;
+    Ok(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_bump_apply() {
+        assert_eq!(Bump::Major.apply(&v("1.2.3")), v("2.0.0"));
+        assert_eq!(Bump::Minor.apply(&v("1.2.3")), v("1.3.0"));
+        assert_eq!(Bump::Patch.apply(&v("1.2.3")), v("1.2.4"));
+        assert_eq!(Bump::Major.apply(&v("2.0.0-rc.1")), v("2.0.0"));
+        assert_eq!(Bump::Major.apply(&v("1.3.0-rc.1")), v("2.0.0"));
+        assert_eq!(Bump::Minor.apply(&v("1.3.0-rc.1")), v("1.3.0"));
+        assert_eq!(Bump::Minor.apply(&v("1.2.4-rc.1")), v("1.3.0"));
+        assert_eq!(Bump::Patch.apply(&v("1.2.4-rc.1")), v("1.2.4"));
+    }
+
+    #[test]
+    fn test_version_spec() {
+        assert_eq!("auto".parse::<VersionSpec>().unwrap(), VersionSpec::Auto);
+        assert_eq!("minor".parse::<VersionSpec>().unwrap(), VersionSpec::Bump(Bump::Minor));
+        assert_eq!("1.0.0".parse::<VersionSpec>().unwrap(), VersionSpec::Exact(v("1.0.0")));
+        assert!("next".parse::<VersionSpec>().is_err());
+    }
+
+    #[test]
+    fn test_bump_between() {
+        assert_eq!(Bump::between(&v("1.2.3"), &v("2.0.0")), Some(Bump::Major));
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.3.0")), Some(Bump::Minor));
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.2.4")), Some(Bump::Patch));
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.2.3")), None);
+    }
+
+    #[test]
+    fn test_pre_release() {
+        let minor = || Ok(Bump::Minor);
+        assert_eq!(pre_release(&v("1.2.3"), "rc", minor).unwrap(), v("1.3.0-rc.1"));
+        assert_eq!(pre_release(&v("1.3.0-rc.1"), "rc", minor).unwrap(), v("1.3.0-rc.2"));
+        assert_eq!(pre_release(&v("1.3.0-beta.2"), "rc", minor).unwrap(), v("1.3.0-rc.1"));
+        assert!(pre_release(&v("1.2.3"), "r c", minor).is_err());
+        assert!(pre_release(&v("1.2.3"), "rc", || anyhow::bail!("no commits")).is_err());
+    }
+}
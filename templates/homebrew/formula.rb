@@ -14,6 +14,7 @@ class {{ classname }} < Formula
   depends_on "shinc"
 
   def install
+    # Homebrew enters the top-level directory of the archive, if there is one.
     bin.install Dir["bin/*"]
     # Install man pages
     man.install Dir["share/man/*"]
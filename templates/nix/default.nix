@@ -0,0 +1,53 @@
+{
+  lib,
+  stdenvNoCC,
+  fetchurl,
+  installShellFiles,
+  bash,
+}:
+
+stdenvNoCC.mkDerivation {
+  pname = "{{ name | nix_string }}";
+  version = "{{ version | nix_string }}";
+
+  src = fetchurl {
+    url = "{{ url | nix_string }}";
+    hash = "{{ hash }}";
+  };
+  sourceRoot = "{% if prefix %}{{ prefix | nix_string }}{% else %}.{% endif %}";
+
+  nativeBuildInputs = [ installShellFiles ];
+  # The shebangs of the bins are patched to use this bash.
+  buildInputs = [ bash ];
+  dontBuild = true;
+
+  installPhase = ''
+    runHook preInstall
+    install -Dm755 -t $out/bin bin/*
+    if [ -d share/man ]; then
+      installManPage share/man/*
+    fi
+    for shell in bash fish zsh; do
+      if [ -d share/completions/$shell ]; then
+        installShellCompletion --$shell share/completions/$shell/*
+      fi
+    done
+    runHook postInstall
+  '';
+
+  meta = {
+    description = "{{ project.description | default(value="") | nix_string }}";
+    {% if project.homepage -%}
+    homepage = "{{ project.homepage | nix_string }}";
+    {% elif project.repository -%}
+    homepage = "{{ project.repository | nix_string }}";
+    {% endif -%}
+    {% if project.license -%}
+    license = lib.getLicenseFromSpdxId "{{ project.license | nix_string }}";
+    {% endif -%}
+    {% if bins | length == 1 -%}
+    mainProgram = "{{ bins[0].name | nix_string }}";
+    {% endif -%}
+    platforms = lib.platforms.all;
+  };
+}
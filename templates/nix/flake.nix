@@ -0,0 +1,25 @@
+{
+  description = "{{ project.description | default(value="") | nix_string }}";
+
+  inputs.nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";
+
+  outputs =
+    { self, nixpkgs }:
+    let
+      systems = [
+        "aarch64-darwin"
+        "aarch64-linux"
+        "x86_64-darwin"
+        "x86_64-linux"
+      ];
+      forAllSystems = f: nixpkgs.lib.genAttrs systems (system: f nixpkgs.legacyPackages.${system});
+    in
+    {
+      packages = forAllSystems (pkgs: {
+        "{{ name | nix_string }}" = pkgs.callPackage (
+          {{ derivation | trim | indent(width=10) }}
+        ) { };
+        default = self.packages.${pkgs.stdenv.hostPlatform.system}."{{ name | nix_string }}";
+      });
+    };
+}
@@ -0,0 +1,15 @@
+## Logging functions
+##
+## Usage:
+##
+##   info "message"
+##   error "message"
+##
+
+info() {
+    printf "%s\n" "$*"
+}
+
+error() {
+    printf "Error: %s\n" "$*" >&2
+}
@@ -0,0 +1,13 @@
+#!/usr/bin/env bash
+
+# @describe {{ description }}
+# @meta version {{ version }}
+# @include lib/common.sh
+
+set -eo pipefail
+
+# @cmd Print a greeting
+# @arg name=world   Who to greet
+hello() {
+    info "Hello, ${argc_name}!"
+}
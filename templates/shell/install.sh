@@ -5,6 +5,7 @@
 # @meta require-tools curl,install,mktemp,tar
 # @flag   -D --debug                            Enable debug mode
 # @flag   -f --force                            Force overwriting an existing binary
+# @flag      --skip-verify                      Skip the signature verification of the archive
 # @option    --tag=latest                       Tag (version) of the binary to install
 # @option    --bin-dir=/usr/local/bin <DIR>     Where to install the binary
 
@@ -55,6 +56,9 @@ cyan_underlined() { print_in_color "\e[4;36m" "$*"; }
 readonly NAME="{{ name }}"
 readonly URL="{{ url }}"
 readonly REPO="${URL#https://github.com/}"
+# Signature of the archives: minisign, ssh or empty if they are not signed
+readonly SIGN_MODE="{{ sign_mode }}"
+readonly PUBLIC_KEY="{{ public_key }}"
 
 # Temporary directory for the installation process
 temp_dir=""
@@ -91,6 +95,28 @@ need() {
     fi
 }
 
+verify_signature() {
+    local file="$1"
+    local url="$2"
+    oh2 "Verifying the ${SIGN_MODE} signature of ${url##*/}"
+    case "${SIGN_MODE}" in
+        minisign)
+            command -v minisign >/dev/null 2>&1 || fatal "need minisign (command not found)"
+            curl -fsSL -o "${file}.minisig" "${url}.minisig"
+            minisign -Vm "${file}" -x "${file}.minisig" -P "${PUBLIC_KEY}" \
+                || fatal "Invalid signature for ${url##*/}."
+            ;;
+        ssh)
+            command -v ssh-keygen >/dev/null 2>&1 || fatal "need ssh-keygen (command not found)"
+            curl -fsSL -o "${file}.sig" "${url}.sig"
+            printf '%s %s\n' "${NAME}" "${PUBLIC_KEY}" >"${temp_dir}/allowed_signers"
+            ssh-keygen -Y verify -f "${temp_dir}/allowed_signers" -I "${NAME}" -n file \
+                -s "${file}.sig" <"${file}" \
+                || fatal "Invalid signature for ${url##*/}."
+            ;;
+    esac
+}
+
 main() {
     oh1 "Installing $(green "${NAME}") from ${URL}"
 
@@ -114,10 +140,21 @@ main() {
     local temp_file="${temp_dir}/${NAME}.tar.gz"
     curl -fsSL -o "${temp_file}" "${archive}"
 
+    if [[ -n "${SIGN_MODE}" ]] && [[ "${argc_skip_verify:-0}" == "0" ]]; then
+        verify_signature "${temp_file}" "${archive}"
+    fi
+
     oh2 "Extracting ${temp_file} archive to ${temp_dir}"
     tar -C ${temp_dir} -zxvf "${temp_file}"
 
+    # The archive may wrap its content in a top-level directory.
     local temp_bin_dir="${temp_dir}/bin"
+    if [[ ! -d "${temp_bin_dir}" ]]; then
+        for p in "${temp_dir}"/*/bin; do
+            temp_bin_dir="$p"
+            break
+        done
+    fi
     if [[ ! -d "${temp_bin_dir}" ]]; then
         fatal "No 'bin' directory found in the archive."
     fi